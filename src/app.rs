@@ -1,15 +1,64 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use egui::{Align, ComboBox, Id, Layout, RichText};
-use rfd::AsyncFileDialog;
+use egui::{Align, ComboBox, Id, Key, KeyboardShortcut, Layout, Modifiers, RichText};
 use tokio::runtime::{Builder, Runtime};
 
 use crate::resource::{CanvasStateResource, GraphResource, ParticleSystemResource};
 // use crate::globals::{CanvasStateResource, GraphResource};
+use crate::canvas::{LodSettings, PresentationStep};
+use crate::document::Document;
+use crate::file_task::{FileTask, FileTaskKind, FileTaskResult};
 use crate::gpu_render::particle::particle_system::ParticleSystem;
-use crate::graph::edge::EdgeType;
-use crate::input::state_manager::InputStateManager;
+use crate::graph::edge::{EdgeRouting, EdgeType};
+use crate::input::state_manager::{InputStateManager, REDO_SHORTCUT, UNDO_SHORTCUT};
+use crate::layout::align::Arrange;
+use crate::layout::force::{force_layout, ForceLayoutConfig};
+use crate::layout::layered::{layered_layout, LayerDirection, LayeredLayoutConfig};
+use crate::layout::tree::{radial_layout, tree_layout, RadialLayoutConfig, TreeLayoutConfig};
+use crate::layout::{node_rects, node_sizes};
+use crate::recovery::{read_recovery, Autosaver, Recovery, AUTOSAVE_INTERVAL};
+use crate::ui::bookmark_panel::BookmarkPanel;
+use crate::ui::canvas::camera::{ZOOM_TO_FIT_SHORTCUT, ZOOM_TO_SELECTION_SHORTCUT};
 use crate::ui::canvas::data::CanvasWidget;
+use crate::ui::canvas::presentation::PRESENTATION_SHORTCUT;
+use crate::ui::note_panel::NotePanel;
+
+/// 最多记住的最近文件数
+const MAX_RECENT_FILES: usize = 10;
+
+/// 文档的修订号：图的撤销状态和设置修订号，以及画布的修订号
+type Revision = ((u64, u64), u64);
+
+pub const SAVE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+pub const SAVE_AS_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::S);
+
+/// 会丢弃当前文档的操作，有未保存的修改时先询问用户
+#[derive(Clone, Debug)]
+enum PendingAction {
+    New,
+    Load,
+    OpenRecent(PathBuf),
+    Quit,
+}
+
+impl PendingAction {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::New => "creating a new project",
+            Self::Load | Self::OpenRecent(_) => "opening another project",
+            Self::Quit => "quitting",
+        }
+    }
+}
+
+/// 未保存修改对话框中用户的选择
+enum UnsavedChoice {
+    Save,
+    Discard,
+    Cancel,
+}
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -25,6 +74,67 @@ pub struct TemplateApp {
     graph_resource: GraphResource,
     #[serde(skip)]
     canvas_widget: CanvasWidget,
+    show_note_panel: bool,
+    show_bookmark_panel: bool,
+    force_layout_config: ForceLayoutConfig,
+    layered_layout_config: LayeredLayoutConfig,
+    tree_layout_config: TreeLayoutConfig,
+    radial_layout_config: RadialLayoutConfig,
+    #[serde(skip)]
+    note_panel: NotePanel,
+    #[serde(skip)]
+    bookmark_panel: BookmarkPanel,
+    /// View 菜单中输入的要飞到的节点编号
+    #[serde(skip)]
+    fly_to_node_id: String,
+    /// 当前文档的路径，还没有保存过时为空
+    document_path: Option<PathBuf>,
+    /// 最近打开或保存的文件，最新的在前
+    recent_files: Vec<PathBuf>,
+    /// 退出时文档是否有未保存的修改，下次启动时恢复修改标记
+    modified: bool,
+    /// 上次保存或打开时的修订号，与当前修订号不同说明有未保存的修改
+    #[serde(skip)]
+    saved_revision: Option<Revision>,
+    /// 正在进行的保存开始时的修订号
+    #[serde(skip)]
+    saving_revision: Option<Revision>,
+    /// 等待用户确认是否保存修改的操作
+    #[serde(skip)]
+    pending_action: Option<PendingAction>,
+    /// 保存成功后继续执行的操作
+    #[serde(skip)]
+    after_save: Option<PendingAction>,
+    /// 用户已经确认退出
+    #[serde(skip)]
+    allow_close: bool,
+    /// 用户丢弃了未保存的修改后退出，应用状态中不保存文档
+    #[serde(skip)]
+    discard_on_exit: bool,
+    /// 上一次设置的窗口标题
+    #[serde(skip)]
+    window_title: String,
+    /// 正在后台进行的保存或打开操作
+    #[serde(skip)]
+    file_task: Option<FileTask>,
+    /// 上一次文件操作的结果，显示在状态栏
+    #[serde(skip)]
+    file_status: String,
+    /// 文件操作失败的信息，显示在对话框中
+    #[serde(skip)]
+    file_error: Option<String>,
+    /// 在后台写入恢复文件
+    #[serde(skip)]
+    autosaver: Autosaver,
+    /// 上一次写入恢复文件时的修订号
+    #[serde(skip)]
+    autosaved_revision: Option<Revision>,
+    /// 上一次检查是否需要自动保存的时间
+    #[serde(skip)]
+    last_autosave: f64,
+    /// 启动时发现的上次没有保存的文档，等待用户决定是否恢复
+    #[serde(skip)]
+    recovery: Option<Recovery>,
     #[serde(skip)]
     particle_system: Option<ParticleSystemResource>,
     #[serde(skip)]
@@ -50,6 +160,32 @@ impl Default for TemplateApp {
             canvas_resource: canvas_resource.clone(),
             graph_resource: graph_resource.clone(),
             canvas_widget: CanvasWidget::new(graph_resource.clone(), canvas_resource.clone()),
+            show_note_panel: true,
+            show_bookmark_panel: false,
+            force_layout_config: ForceLayoutConfig::default(),
+            layered_layout_config: LayeredLayoutConfig::default(),
+            tree_layout_config: TreeLayoutConfig::default(),
+            radial_layout_config: RadialLayoutConfig::default(),
+            note_panel: NotePanel::new(graph_resource.clone()),
+            bookmark_panel: BookmarkPanel::new(graph_resource.clone(), canvas_resource.clone()),
+            fly_to_node_id: String::new(),
+            document_path: None,
+            recent_files: Vec::new(),
+            modified: false,
+            saved_revision: Some(((0, 0), 0)),
+            saving_revision: None,
+            pending_action: None,
+            after_save: None,
+            allow_close: false,
+            discard_on_exit: false,
+            window_title: String::new(),
+            file_task: None,
+            file_status: String::new(),
+            file_error: None,
+            autosaver: Autosaver::default(),
+            autosaved_revision: None,
+            last_autosave: 0.0,
+            recovery: None,
             particle_system: None,
             runtime: Builder::new_multi_thread()
                 .worker_threads(1)
@@ -79,6 +215,11 @@ impl TemplateApp {
                 eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
             app.canvas_widget =
                 CanvasWidget::new(app.graph_resource.clone(), app.canvas_resource.clone());
+            app.note_panel = NotePanel::new(app.graph_resource.clone());
+            app.bookmark_panel =
+                BookmarkPanel::new(app.graph_resource.clone(), app.canvas_resource.clone());
+            // 修订号不会保存，上次退出时有未保存的修改就保持修改标记
+            app.saved_revision = (!app.modified).then(|| app.revision());
             // println!("app: {:?}", app);
             app
         } else {
@@ -86,6 +227,16 @@ impl TemplateApp {
         };
         // let mut app: TemplateApp = Default::default();
 
+        // 上次没有正常退出时会留下恢复文件
+        match read_recovery() {
+            Some(Ok(recovery)) => app.recovery = Some(recovery),
+            Some(Err(e)) => {
+                log::warn!("discarding unreadable recovery file: {e}");
+                app.autosaver.remove();
+            }
+            None => {}
+        }
+
         let wgpu_render_state = cc.wgpu_render_state.as_ref();
         if let Some(rs) = wgpu_render_state {
             let device = &rs.device;
@@ -115,6 +266,406 @@ impl TemplateApp {
         app
     }
 
+    /// 图和画布的修订号
+    fn revision(&self) -> Revision {
+        (
+            self.graph_resource.read_resource(|graph| graph.revision()),
+            self.canvas_resource
+                .read_resource(|canvas_state| canvas_state.revision),
+        )
+    }
+
+    fn is_modified(&self) -> bool {
+        self.saved_revision != Some(self.revision())
+    }
+
+    /// 窗口标题中显示的文档名
+    fn document_name(&self) -> String {
+        self.document_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map_or("Untitled".to_owned(), |name| {
+                name.to_string_lossy().into_owned()
+            })
+    }
+
+    fn add_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// 执行会丢弃当前文档的操作，有未保存的修改时先询问
+    fn request_action(&mut self, ctx: &egui::Context, action: PendingAction) {
+        if self.is_modified() {
+            self.pending_action = Some(action);
+        } else {
+            self.perform_action(ctx, action);
+        }
+    }
+
+    fn perform_action(&mut self, ctx: &egui::Context, action: PendingAction) {
+        match action {
+            PendingAction::New => {
+                self.open_document(Document::new(
+                    GraphResource::default(),
+                    CanvasStateResource::default(),
+                ));
+                self.document_path = None;
+                self.file_status.clear();
+            }
+            PendingAction::Load => {
+                self.file_task = Some(FileTask::load(&self.runtime, ctx, None));
+            }
+            PendingAction::OpenRecent(path) => {
+                self.file_task = Some(FileTask::load(&self.runtime, ctx, Some(path)));
+            }
+            PendingAction::Quit => {
+                // 用户选择了不保存，退出时保存的应用状态不能带上丢弃的修改
+                self.discard_on_exit = self.is_modified();
+                self.allow_close = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    /// 在后台保存当前文档的副本，`save_as` 或者还没有路径时先选择保存位置
+    fn start_save(&mut self, ctx: &egui::Context, save_as: bool) {
+        // 已经有文件操作在进行时不能覆盖它，保存完成后再处理 `after_save`
+        if self.file_task.is_some() {
+            return;
+        }
+        let path = if save_as {
+            None
+        } else {
+            self.document_path.clone()
+        };
+        match Document::snapshot(&self.graph_resource, &self.canvas_resource) {
+            Ok(document) => {
+                self.saving_revision = Some(self.revision());
+                self.file_task = Some(FileTask::save(&self.runtime, ctx, document, path));
+            }
+            Err(e) => {
+                self.after_save = None;
+                self.file_error = Some(format!("Could not save the project: {e}"));
+            }
+        }
+    }
+
+    /// 处理后台文件操作的进度和结果
+    fn poll_file_task(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.file_task.as_mut().and_then(FileTask::poll) else {
+            return;
+        };
+        self.file_task = None;
+        match result {
+            FileTaskResult::Saved(path) => {
+                self.saved_revision = self.saving_revision.take();
+                // 保存期间没有新的修改，恢复文件就不再需要了
+                if !self.is_modified() {
+                    self.autosaver.remove();
+                    self.autosaved_revision = None;
+                }
+                self.file_status = format!("Saved {}", path.display());
+                self.document_path = Some(path.clone());
+                self.add_recent_file(path);
+                if self.after_save.is_some() && self.is_modified() {
+                    // 这次保存开始后又有修改，先保存它们再继续
+                    self.start_save(ctx, false);
+                } else if let Some(action) = self.after_save.take() {
+                    self.perform_action(ctx, action);
+                }
+            }
+            FileTaskResult::Loaded(path, document) => {
+                // 打开了别的文档，等待保存后执行的操作已经不适用
+                self.after_save = None;
+                self.open_document(document);
+                self.file_status = format!("Opened {}", path.display());
+                self.document_path = Some(path.clone());
+                self.add_recent_file(path);
+            }
+            FileTaskResult::Cancelled => {
+                self.saving_revision = None;
+                self.after_save = None;
+            }
+            FileTaskResult::Failed(message) => {
+                self.saving_revision = None;
+                self.after_save = None;
+                self.file_error = Some(message);
+            }
+        }
+    }
+
+    /// 文件菜单的快捷键
+    fn handle_file_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.file_task.is_some() {
+            return;
+        }
+        // 先检查带 Shift 的另存为，否则会被保存的快捷键吃掉
+        if ctx.input_mut(|i| i.consume_shortcut(&SAVE_AS_SHORTCUT)) {
+            self.start_save(ctx, true);
+        } else if ctx.input_mut(|i| i.consume_shortcut(&SAVE_SHORTCUT)) {
+            self.start_save(ctx, false);
+        }
+    }
+
+    /// 有未保存的修改时拦截关闭窗口，先询问用户
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.viewport().close_requested()) && !self.allow_close && self.is_modified()
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.pending_action = Some(PendingAction::Quit);
+        }
+    }
+
+    /// 窗口标题显示文档名，有未保存的修改时加上星号
+    fn update_title(&mut self, ctx: &egui::Context) {
+        let marker = if self.is_modified() { "*" } else { "" };
+        let title = format!("{marker}{} - Cognitheon", self.document_name());
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    /// 询问是否保存修改的对话框
+    fn unsaved_changes_modal(&mut self, ctx: &egui::Context) {
+        let Some(action) = &self.pending_action else {
+            return;
+        };
+        let message = format!(
+            "Do you want to save the changes to {} before {}?",
+            self.document_name(),
+            action.description()
+        );
+        let response = egui::Modal::new(Id::new("unsaved_changes")).show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            ui.heading("Unsaved Changes");
+            ui.add_space(8.0);
+            ui.label(message);
+            ui.add_space(8.0);
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("Save").clicked() {
+                    Some(UnsavedChoice::Save)
+                } else if ui.button("Don't Save").clicked() {
+                    Some(UnsavedChoice::Discard)
+                } else if ui.button("Cancel").clicked() {
+                    Some(UnsavedChoice::Cancel)
+                } else {
+                    None
+                }
+            })
+            .inner
+        });
+        let should_close = response.should_close();
+        let choice = response
+            .inner
+            .or_else(|| should_close.then_some(UnsavedChoice::Cancel));
+        let Some(choice) = choice else {
+            return;
+        };
+        let Some(action) = self.pending_action.take() else {
+            return;
+        };
+        match choice {
+            UnsavedChoice::Save => {
+                self.after_save = Some(action);
+                self.start_save(ctx, false);
+            }
+            UnsavedChoice::Discard => self.perform_action(ctx, action),
+            UnsavedChoice::Cancel => {}
+        }
+    }
+
+    /// 用打开的文档替换当前的图和画布，替换后没有未保存的修改
+    fn open_document(&mut self, document: Document) {
+        self.graph_resource = document.graph;
+        self.canvas_resource = document.canvas;
+
+        self.canvas_widget =
+            CanvasWidget::new(self.graph_resource.clone(), self.canvas_resource.clone());
+        self.note_panel = NotePanel::new(self.graph_resource.clone());
+        self.bookmark_panel =
+            BookmarkPanel::new(self.graph_resource.clone(), self.canvas_resource.clone());
+        self.saved_revision = Some(self.revision());
+        self.autosaver.remove();
+        self.autosaved_revision = None;
+    }
+
+    /// 每隔一段时间把有未保存修改的文档写入恢复文件
+    fn autosave(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        // 等待用户决定是否恢复时不能覆盖上次的恢复文件
+        if now - self.last_autosave < AUTOSAVE_INTERVAL || self.recovery.is_some() {
+            return;
+        }
+        self.last_autosave = now;
+        self.write_recovery();
+    }
+
+    /// 当前文档有新的未保存修改时写入恢复文件
+    fn write_recovery(&mut self) {
+        let revision = self.revision();
+        if !self.is_modified() || self.autosaved_revision == Some(revision) {
+            return;
+        }
+        match Document::snapshot(&self.graph_resource, &self.canvas_resource) {
+            Ok(document) => {
+                self.autosaver
+                    .write(&self.runtime, self.document_path.clone(), document);
+                self.autosaved_revision = Some(revision);
+            }
+            Err(e) => log::warn!("autosave failed: {e}"),
+        }
+    }
+
+    /// 启动时询问是否恢复上次没有保存的文档
+    fn recovery_modal(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = &self.recovery else {
+            return;
+        };
+        let name = recovery
+            .document_path
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map_or_else(
+                || "an untitled project".to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+        let response = egui::Modal::new(Id::new("recovery")).show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            ui.heading("Recover Unsaved Work");
+            ui.add_space(8.0);
+            ui.label(format!(
+                "Cognitheon did not shut down properly. Unsaved changes to {name} were recovered. \
+                 Do you want to restore them?"
+            ));
+            ui.add_space(8.0);
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("Restore").clicked() {
+                    Some(true)
+                } else if ui.button("Discard").clicked() {
+                    Some(false)
+                } else {
+                    None
+                }
+            })
+            .inner
+        });
+        // 必须明确选择，点击对话框外面不会丢弃恢复的内容
+        let Some(restore) = response.inner else {
+            return;
+        };
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        if restore {
+            self.open_document(recovery.document);
+            // 恢复的内容还没有保存到文档文件中
+            self.saved_revision = None;
+            self.document_path = recovery.document_path;
+            self.file_status = "Restored unsaved changes".to_owned();
+            self.write_recovery();
+        } else {
+            self.autosaver.remove();
+        }
+    }
+
+    /// 文件操作失败时显示的对话框
+    fn file_error_modal(&mut self, ctx: &egui::Context) {
+        let Some(message) = &self.file_error else {
+            return;
+        };
+        let response = egui::Modal::new(Id::new("file_error")).show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            ui.heading("File Error");
+            ui.add_space(8.0);
+            ui.label(message);
+            ui.add_space(8.0);
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                ui.button("OK").clicked()
+            })
+            .inner
+        });
+        if response.inner || response.should_close() {
+            self.file_error = None;
+        }
+    }
+
+    /// 对整个图或选中的节点运行力导向布局
+    fn run_force_layout(&self, selection_only: bool) {
+        self.graph_resource.with_resource(|graph| {
+            let sizes = node_sizes(graph);
+            let movable = if selection_only {
+                graph.get_selected_nodes()
+            } else {
+                graph.graph.node_indices().collect()
+            };
+            let positions = force_layout(&graph.graph, &sizes, &movable, &self.force_layout_config);
+            graph.apply_layout(positions, self.force_layout_config.animate);
+        });
+    }
+
+    /// 对整个图或选中的节点运行分层布局，贝塞尔边的控制柄沿分层方向摆放
+    fn run_layered_layout(&self, selection_only: bool) {
+        self.graph_resource.with_resource(|graph| {
+            let sizes = node_sizes(graph);
+            let nodes = if selection_only {
+                graph.get_selected_nodes()
+            } else {
+                graph.graph.node_indices().collect()
+            };
+            let config = &self.layered_layout_config;
+            let layout = layered_layout(&graph.graph, &sizes, &nodes, config);
+            let direction = config.direction.vector();
+            let handles: Vec<_> = layout
+                .edges
+                .iter()
+                .map(|&(edge_index, forward)| {
+                    (edge_index, if forward { direction } else { -direction })
+                })
+                .collect();
+
+            // 控制柄和节点位置作为一个撤销步骤；先改控制柄，避免打断布局动画
+            graph.history.begin_batch();
+            graph.orient_bezier_handles(&handles);
+            graph.apply_layout(layout.positions, config.animate);
+            graph.history.end_batch();
+        });
+    }
+
+    /// 以唯一选中的节点为根运行树形或径向布局
+    fn run_tree_layout(&self, radial: bool) {
+        self.graph_resource.with_resource(|graph| {
+            let [root] = graph.get_selected_nodes()[..] else {
+                return;
+            };
+            let sizes = node_sizes(graph);
+            let (positions, animate) = if radial {
+                let config = &self.radial_layout_config;
+                (
+                    radial_layout(&graph.graph, &sizes, root, config),
+                    config.animate,
+                )
+            } else {
+                let config = &self.tree_layout_config;
+                (
+                    tree_layout(&graph.graph, &sizes, root, config),
+                    config.animate,
+                )
+            };
+            graph.apply_layout(positions, animate);
+        });
+    }
+
+    /// 对齐、分布选中的节点或统一它们的宽度
+    fn arrange_selection(&self, arrange: Arrange) {
+        self.graph_resource.with_resource(|graph| {
+            let rects = node_rects(graph, &graph.get_selected_nodes());
+            graph.arrange_nodes(&rects, arrange);
+        });
+    }
+
     // pub fn get_graph(ctx: &egui::Context) -> &Graph {
     //     ctx.data(|data| {
     //         let app = data
@@ -130,9 +681,23 @@ impl eframe::App for TemplateApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         // println!("save");
         // println!("self: {:?}", self);
+        if self.discard_on_exit {
+            // 不重新读取文件，下次启动时是空文档，之前自动保存的应用状态也被覆盖
+            self.graph_resource = GraphResource::default();
+            self.canvas_resource = CanvasStateResource::default();
+            self.document_path = None;
+            self.modified = false;
+        } else {
+            self.modified = self.is_modified();
+        }
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
+    /// 正常退出时删除恢复文件，未保存的修改已经由用户确认丢弃
+    fn on_exit(&mut self) {
+        self.autosaver.remove();
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let last_offset: f32 =
@@ -148,6 +713,16 @@ impl eframe::App for TemplateApp {
         // 每帧更新 offset
         let new_offset = last_offset - speed * delta_time;
         ctx.data_mut(|m| m.insert_temp(Id::new("animation_offset"), new_offset));
+
+        self.poll_file_task(ctx);
+        self.handle_file_shortcuts(ctx);
+        self.handle_close_request(ctx);
+        self.autosave(ctx);
+
+        self.graph_resource
+            .with_resource(|graph| graph.step_layout_animation(delta_time));
+        self.canvas_resource
+            .with_resource(|canvas_state| canvas_state.step_camera_animation(delta_time));
         // println!(
         //     "update: {:?}",
         //     self.graph_resource.0.read().unwrap().graph.node_count()
@@ -168,7 +743,9 @@ impl eframe::App for TemplateApp {
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+        // 演示时只显示画布
+        let presenting = self.canvas_widget.is_presenting();
+        egui::TopBottomPanel::top("top_panel").show_animated(ctx, !presenting, |ui| {
             // The top panel is often a good place for a menu bar:
 
             egui::menu::bar(ui, |ui| {
@@ -176,69 +753,346 @@ impl eframe::App for TemplateApp {
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
                     ui.menu_button("File", |ui| {
-                        if ui.button("New").clicked() {
-                            println!("new");
-                            self.graph_resource.with_resource(|graph| graph.reset());
+                        let idle = self.file_task.is_none();
+                        if ui.add_enabled(idle, egui::Button::new("New")).clicked() {
+                            ui.close_menu();
+                            self.request_action(ctx, PendingAction::New);
                         }
 
-                        if ui.button("Save").clicked() {
+                        if ui.add_enabled(idle, egui::Button::new("Open…")).clicked() {
                             ui.close_menu();
-                            println!("save");
-                            let future = async {
-                                let file = AsyncFileDialog::new()
-                                    .add_filter("Cognitheon", &["cnt"])
-                                    .set_directory("~")
-                                    .save_file()
-                                    .await;
-
-                                let data = serde_json::to_string(&self).unwrap();
-                                let file = file.unwrap();
-                                match file.write(data.as_bytes()).await {
-                                    Ok(_) => println!("save success"),
-                                    Err(e) => println!("save failed: {}", e),
+                            self.request_action(ctx, PendingAction::Load);
+                        }
+
+                        ui.add_enabled_ui(idle && !self.recent_files.is_empty(), |ui| {
+                            ui.menu_button("Open Recent", |ui| {
+                                let mut open = None;
+                                for path in &self.recent_files {
+                                    if ui.button(path.display().to_string()).clicked() {
+                                        open = Some(path.clone());
+                                    }
+                                }
+                                ui.separator();
+                                if ui.button("Clear Recent").clicked() {
+                                    ui.close_menu();
+                                    self.recent_files.clear();
+                                }
+                                if let Some(path) = open {
+                                    ui.close_menu();
+                                    self.request_action(ctx, PendingAction::OpenRecent(path));
                                 }
-                            };
-                            self.runtime.block_on(future);
+                            });
+                        });
+
+                        ui.separator();
+                        if ui
+                            .add_enabled(
+                                idle,
+                                egui::Button::new("Save")
+                                    .shortcut_text(ctx.format_shortcut(&SAVE_SHORTCUT)),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.start_save(ctx, false);
                         }
 
-                        if ui.button("Load").clicked() {
-                            println!("load file");
+                        if ui
+                            .add_enabled(
+                                idle,
+                                egui::Button::new("Save As…")
+                                    .shortcut_text(ctx.format_shortcut(&SAVE_AS_SHORTCUT)),
+                            )
+                            .clicked()
+                        {
                             ui.close_menu();
-                            let future = async {
-                                let file = AsyncFileDialog::new()
-                                    .add_filter("Cognitheon", &["cnt"])
-                                    .set_directory("~")
-                                    .pick_file()
-                                    .await;
-
-                                let file = file.unwrap();
-                                let data = file.read().await;
-
-                                data
-                            };
-                            let data = self.runtime.block_on(future);
-                            match serde_json::from_slice::<TemplateApp>(&data) {
-                                Ok(app) => {
-                                    self.graph_resource = app.graph_resource;
-                                    self.canvas_resource = app.canvas_resource;
-
-                                    self.canvas_widget = CanvasWidget::new(
-                                        self.graph_resource.clone(),
-                                        self.canvas_resource.clone(),
-                                    );
-                                }
-                                Err(e) => println!("load failed: {}", e),
-                            }
+                            self.start_save(ctx, true);
                         }
 
+                        ui.separator();
                         if ui.button("Quit").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                            ui.close_menu();
+                            self.request_action(ctx, PendingAction::Quit);
                         }
                     });
 
                     ui.add_space(16.0);
                 }
 
+                ui.menu_button("Edit", |ui| {
+                    let (can_undo, can_redo) = self.graph_resource.read_resource(|graph| {
+                        (graph.history.can_undo(), graph.history.can_redo())
+                    });
+                    if ui
+                        .add_enabled(
+                            can_undo,
+                            egui::Button::new("Undo")
+                                .shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.graph_resource.with_resource(|graph| graph.undo());
+                    }
+                    if ui
+                        .add_enabled(
+                            can_redo,
+                            egui::Button::new("Redo")
+                                .shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.graph_resource.with_resource(|graph| graph.redo());
+                    }
+
+                    ui.separator();
+                    let selected_edges = self
+                        .graph_resource
+                        .read_resource(|graph| graph.get_selected_edges());
+                    if ui
+                        .add_enabled(
+                            !selected_edges.is_empty(),
+                            egui::Button::new("Auto-route Selected Edges"),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.graph_resource.with_resource(|graph| {
+                            graph.set_edge_routing(&selected_edges, EdgeRouting::Automatic)
+                        });
+                    }
+                    if ui
+                        .add_enabled(
+                            !selected_edges.is_empty(),
+                            egui::Button::new("Pin Selected Edges"),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.graph_resource.with_resource(|graph| {
+                            graph.set_edge_routing(&selected_edges, EdgeRouting::Pinned)
+                        });
+                    }
+                    ui.add_enabled_ui(!selected_edges.is_empty(), |ui| {
+                        ui.menu_button("Set Edge Type", |ui| {
+                            for edge_type in EdgeType::ALL {
+                                if ui.button(edge_type.to_string()).clicked() {
+                                    ui.close_menu();
+                                    self.graph_resource.with_resource(|graph| {
+                                        graph.set_edge_type(&selected_edges, edge_type)
+                                    });
+                                }
+                            }
+                        });
+                    });
+                });
+
+                ui.menu_button("Layout", |ui| {
+                    let has_selection = self
+                        .graph_resource
+                        .read_resource(|graph| graph.get_selected_nodes().len() > 1);
+                    ui.menu_button("Force-Directed", |ui| {
+                        force_layout_settings(ui, &mut self.force_layout_config);
+                        ui.separator();
+                        if ui.button("Apply to All").clicked() {
+                            ui.close_menu();
+                            self.run_force_layout(false);
+                        }
+                        if ui
+                            .add_enabled(has_selection, egui::Button::new("Apply to Selection"))
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.run_force_layout(true);
+                        }
+                    });
+                    ui.menu_button("Layered", |ui| {
+                        layered_layout_settings(ui, &mut self.layered_layout_config);
+                        ui.separator();
+                        if ui.button("Apply to All").clicked() {
+                            ui.close_menu();
+                            self.run_layered_layout(false);
+                        }
+                        if ui
+                            .add_enabled(has_selection, egui::Button::new("Apply to Selection"))
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.run_layered_layout(true);
+                        }
+                    });
+                    let has_root = self
+                        .graph_resource
+                        .read_resource(|graph| graph.get_selected_nodes().len() == 1);
+                    ui.menu_button("Tree", |ui| {
+                        tree_layout_settings(ui, &mut self.tree_layout_config);
+                        ui.separator();
+                        if ui
+                            .add_enabled(has_root, egui::Button::new("Apply from Selected Root"))
+                            .on_disabled_hover_text("Select a single node as the root")
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.run_tree_layout(false);
+                        }
+                    });
+                    ui.menu_button("Radial", |ui| {
+                        radial_layout_settings(ui, &mut self.radial_layout_config);
+                        ui.separator();
+                        if ui
+                            .add_enabled(has_root, egui::Button::new("Apply from Selected Root"))
+                            .on_disabled_hover_text("Select a single node as the root")
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.run_tree_layout(true);
+                        }
+                    });
+                });
+
+                ui.menu_button("Arrange", |ui| {
+                    let selected_count = self
+                        .graph_resource
+                        .read_resource(|graph| graph.get_selected_nodes().len());
+                    for arrange in Arrange::ALL {
+                        if arrange.starts_group() {
+                            ui.separator();
+                        }
+                        let button = egui::Button::new(arrange.label())
+                            .shortcut_text(ctx.format_shortcut(&arrange.shortcut()));
+                        if ui
+                            .add_enabled(selected_count >= arrange.min_nodes(), button)
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.arrange_selection(arrange);
+                        }
+                    }
+                });
+
+                ui.menu_button("Present", |ui| {
+                    let (has_path, selected_ids) = (
+                        self.canvas_resource
+                            .read_resource(|cs| !cs.presentation.is_empty()),
+                        self.graph_resource.read_resource(|graph| {
+                            graph
+                                .get_selected_nodes()
+                                .into_iter()
+                                .filter_map(|node_index| graph.get_node(node_index))
+                                .map(|node| node.id)
+                                .collect::<Vec<u64>>()
+                        }),
+                    );
+                    if ui
+                        .add_enabled(
+                            has_path,
+                            egui::Button::new("Start Presentation")
+                                .shortcut_text(ctx.format_shortcut(&PRESENTATION_SHORTCUT)),
+                        )
+                        .on_hover_text("Arrow keys step through the path, Esc ends it")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.canvas_widget.start_presentation(ctx);
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(
+                            !selected_ids.is_empty(),
+                            egui::Button::new("Add Selected Nodes to Path"),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.canvas_resource.with_resource(|cs| {
+                            cs.presentation
+                                .extend(selected_ids.into_iter().map(PresentationStep::Node));
+                            cs.mark_changed();
+                        });
+                    }
+                    if ui.button("Add Current View to Path").clicked() {
+                        ui.close_menu();
+                        self.canvas_resource
+                            .with_resource(|cs| cs.add_presentation_view());
+                    }
+                    if ui
+                        .add_enabled(has_path, egui::Button::new("Clear Path"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.canvas_resource.with_resource(|cs| {
+                            cs.presentation.clear();
+                            cs.mark_changed();
+                        });
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    if ui
+                        .add(
+                            egui::Button::new("Zoom to Fit")
+                                .shortcut_text(ctx.format_shortcut(&ZOOM_TO_FIT_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.canvas_widget.zoom_to_fit();
+                    }
+                    if ui
+                        .add(
+                            egui::Button::new("Zoom to Selection")
+                                .shortcut_text(ctx.format_shortcut(&ZOOM_TO_SELECTION_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.canvas_widget.zoom_to_selection();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Fly to Node");
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.fly_to_node_id)
+                                .desired_width(50.0)
+                                .hint_text("id"),
+                        );
+                        let submitted =
+                            response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        let node_id = self.fly_to_node_id.trim().parse::<u64>().ok();
+                        let go = ui.add_enabled(node_id.is_some(), egui::Button::new("Go"));
+                        if let Some(node_id) = node_id.filter(|_| submitted || go.clicked()) {
+                            if self.canvas_widget.fly_to_node(node_id) {
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.checkbox(&mut self.show_note_panel, "Notes Panel");
+                    ui.checkbox(&mut self.show_bookmark_panel, "Bookmarks Panel");
+                    let mut show_minimap = self.canvas_resource.read_resource(|cs| cs.show_minimap);
+                    if ui.checkbox(&mut show_minimap, "Minimap").changed() {
+                        self.canvas_resource
+                            .with_resource(|cs| cs.show_minimap = show_minimap);
+                    }
+                    ui.menu_button("Level of Detail", |ui| {
+                        let mut lod = self.canvas_resource.read_resource(|cs| cs.lod);
+                        lod_settings(ui, &mut lod);
+                        self.canvas_resource.with_resource(|cs| cs.lod = lod);
+                    });
+                    ui.separator();
+                    let (mut snap_to_grid, mut smart_guides) = self
+                        .canvas_resource
+                        .read_resource(|cs| (cs.snap_to_grid, cs.smart_guides));
+                    let snap_changed = ui.checkbox(&mut snap_to_grid, "Snap to Grid").changed();
+                    let guides_changed = ui.checkbox(&mut smart_guides, "Smart Guides").changed();
+                    if snap_changed || guides_changed {
+                        self.canvas_resource.with_resource(|cs| {
+                            cs.snap_to_grid = snap_to_grid;
+                            cs.smart_guides = smart_guides;
+                        });
+                    }
+                });
+
                 egui::widgets::global_theme_preference_buttons(ui);
                 // 获取全局主题
                 // let theme = ui.ctx().theme();
@@ -251,37 +1105,52 @@ impl eframe::App for TemplateApp {
                     // });
                 }
 
-                let mut edge_type = self
-                    .graph_resource
-                    .read_resource(|graph| graph.edge_type.clone());
-                ComboBox::from_label("Edge Type")
-                    .selected_text(format!("{:?}", edge_type))
+                // 新建边的默认样式，已有的边通过 Edit > Set Edge Type 修改
+                let mut edge_type = self.graph_resource.read_resource(|graph| graph.edge_type);
+                ComboBox::from_label("Default Edge Type")
+                    .selected_text(edge_type.to_string())
                     .show_ui(ui, |ui| {
-                        if ui
-                            .selectable_value(&mut edge_type, EdgeType::Bezier, "Bezier")
-                            .clicked()
-                        {
-                            self.graph_resource
-                                .with_resource(|graph| graph.edge_type = EdgeType::Bezier);
-                        }
-                        if ui
-                            .selectable_value(&mut edge_type, EdgeType::Line, "Line")
-                            .clicked()
-                        {
-                            self.graph_resource
-                                .with_resource(|graph| graph.edge_type = EdgeType::Line);
+                        for option in EdgeType::ALL {
+                            if ui
+                                .selectable_value(&mut edge_type, option, option.to_string())
+                                .clicked()
+                            {
+                                self.graph_resource
+                                    .with_resource(|graph| graph.set_default_edge_type(option));
+                            }
                         }
                     });
+
+                let mut rotate_edge_labels = self
+                    .graph_resource
+                    .read_resource(|graph| graph.rotate_edge_labels);
+                if ui
+                    .checkbox(&mut rotate_edge_labels, "Rotate Edge Labels")
+                    .changed()
+                {
+                    self.graph_resource
+                        .with_resource(|graph| graph.set_rotate_edge_labels(rotate_edge_labels));
+                }
             });
         });
 
-        egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
+        egui::TopBottomPanel::bottom("bottom_panel").show_animated(ctx, !presenting, |ui| {
             ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
                 ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
                     current_zoom(ui, &self.canvas_resource);
                     current_offset(ui, &self.canvas_resource);
                     current_input_state(ui, &self.canvas_widget.input_manager);
                     current_fps(ui, &self.canvas_widget.input_manager);
+                    if let Some(task) = &self.file_task {
+                        ui.spinner();
+                        let action = match task.kind {
+                            FileTaskKind::Save => "Saving",
+                            FileTaskKind::Load => "Opening",
+                        };
+                        ui.label(format!("{action}: {}…", task.progress));
+                    } else if !self.file_status.is_empty() {
+                        ui.label(&self.file_status);
+                    }
                 });
                 ui.end_row();
                 ui.with_layout(
@@ -306,6 +1175,13 @@ impl eframe::App for TemplateApp {
             });
         });
 
+        if self.show_note_panel && !presenting {
+            self.note_panel.show(ctx);
+        }
+        if self.show_bookmark_panel && !presenting {
+            self.bookmark_panel.show(ctx);
+        }
+
         egui::CentralPanel::default()
             // .frame(egui::Frame::default().outer_margin(egui::Margin::same(3.0)))
             .show(ctx, |ui| {
@@ -318,6 +1194,11 @@ impl eframe::App for TemplateApp {
                 //     });
             });
 
+        self.recovery_modal(ctx);
+        self.unsaved_changes_modal(ctx);
+        self.file_error_modal(ctx);
+        self.update_title(ctx);
+
         // ctx.show_viewport_deferred(
         //     ViewportId::from_hash_of("test"),
         //     ViewportBuilder::default().with_title("testwindow"),
@@ -330,6 +1211,144 @@ impl eframe::App for TemplateApp {
     }
 }
 
+fn force_layout_settings(ui: &mut egui::Ui, config: &mut ForceLayoutConfig) {
+    egui::Grid::new("force_layout_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Iterations");
+            ui.add(egui::DragValue::new(&mut config.iterations).range(1..=2000));
+            ui.end_row();
+            ui.label("Ideal distance");
+            ui.add(egui::DragValue::new(&mut config.ideal_distance).range(10.0..=500.0));
+            ui.end_row();
+            ui.label("Repulsion");
+            ui.add(
+                egui::DragValue::new(&mut config.repulsion)
+                    .speed(0.01)
+                    .range(0.0..=10.0),
+            );
+            ui.end_row();
+            ui.label("Attraction");
+            ui.add(
+                egui::DragValue::new(&mut config.attraction)
+                    .speed(0.01)
+                    .range(0.0..=10.0),
+            );
+            ui.end_row();
+            ui.label("Gravity");
+            ui.add(
+                egui::DragValue::new(&mut config.gravity)
+                    .speed(0.001)
+                    .range(0.0..=1.0),
+            );
+            ui.end_row();
+        });
+    ui.checkbox(&mut config.animate, "Animate");
+    if ui.button("Reset to Defaults").clicked() {
+        *config = ForceLayoutConfig::default();
+    }
+}
+
+fn lod_settings(ui: &mut egui::Ui, lod: &mut LodSettings) {
+    egui::Grid::new("lod_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Boxes below zoom");
+            ui.add(
+                egui::DragValue::new(&mut lod.boxes_below)
+                    .speed(0.01)
+                    .range(0.0..=2.0),
+            );
+            ui.end_row();
+            ui.label("Dots below zoom");
+            ui.add(
+                egui::DragValue::new(&mut lod.dots_below)
+                    .speed(0.01)
+                    .range(0.0..=2.0),
+            );
+            ui.end_row();
+        });
+    // 色块的阈值不能高于方框的阈值
+    lod.dots_below = lod.dots_below.min(lod.boxes_below);
+    if ui.button("Reset to Defaults").clicked() {
+        *lod = LodSettings::default();
+    }
+}
+
+fn layered_layout_settings(ui: &mut egui::Ui, config: &mut LayeredLayoutConfig) {
+    egui::Grid::new("layered_layout_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Direction");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut config.direction, LayerDirection::TopDown, "Top-Down");
+                ui.selectable_value(
+                    &mut config.direction,
+                    LayerDirection::LeftRight,
+                    "Left-Right",
+                );
+            });
+            ui.end_row();
+            ui.label("Layer gap");
+            ui.add(egui::DragValue::new(&mut config.layer_gap).range(10.0..=500.0));
+            ui.end_row();
+            ui.label("Node gap");
+            ui.add(egui::DragValue::new(&mut config.node_gap).range(0.0..=500.0));
+            ui.end_row();
+            ui.label("Ordering sweeps");
+            ui.add(egui::DragValue::new(&mut config.sweeps).range(0..=200));
+            ui.end_row();
+        });
+    ui.checkbox(&mut config.animate, "Animate");
+    if ui.button("Reset to Defaults").clicked() {
+        *config = LayeredLayoutConfig::default();
+    }
+}
+
+fn tree_layout_settings(ui: &mut egui::Ui, config: &mut TreeLayoutConfig) {
+    egui::Grid::new("tree_layout_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Direction");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut config.direction, LayerDirection::TopDown, "Top-Down");
+                ui.selectable_value(
+                    &mut config.direction,
+                    LayerDirection::LeftRight,
+                    "Left-Right",
+                );
+            });
+            ui.end_row();
+            ui.label("Level gap");
+            ui.add(egui::DragValue::new(&mut config.level_gap).range(10.0..=500.0));
+            ui.end_row();
+            ui.label("Sibling gap");
+            ui.add(egui::DragValue::new(&mut config.sibling_gap).range(0.0..=500.0));
+            ui.end_row();
+        });
+    ui.checkbox(&mut config.animate, "Animate");
+    if ui.button("Reset to Defaults").clicked() {
+        *config = TreeLayoutConfig::default();
+    }
+}
+
+fn radial_layout_settings(ui: &mut egui::Ui, config: &mut RadialLayoutConfig) {
+    egui::Grid::new("radial_layout_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Ring gap");
+            ui.add(egui::DragValue::new(&mut config.ring_gap).range(10.0..=500.0));
+            ui.end_row();
+            ui.label("Node gap");
+            ui.add(egui::DragValue::new(&mut config.node_gap).range(0.0..=500.0));
+            ui.end_row();
+        });
+    ui.checkbox(&mut config.animate, "Animate");
+    if ui.button("Reset to Defaults").clicked() {
+        *config = RadialLayoutConfig::default();
+    }
+}
+
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
@@ -2,24 +2,166 @@ use std::sync::atomic::{AtomicU64, Ordering};
 
 use egui::emath::TSTransform;
 
+/// 相机动画的时长（秒）
+pub const CAMERA_ANIMATION_DURATION: f32 = 0.4;
+
+/// 缩放到适合时视口四周留出的边距（屏幕坐标）
+const FIT_MARGIN: f32 = 40.0;
+
+/// 缩放到适合时最多放大到的比例，避免单个小节点被放得过大
+const MAX_FIT_SCALING: f32 = 2.0;
+
+/// 从一个画布变换平滑过渡到另一个
+///
+/// 视口中心在画布坐标中线性移动，缩放按对数插值，放大和缩小的速度看起来一致。
+#[derive(Clone, Debug)]
+pub struct CameraAnimation {
+    from: TSTransform,
+    to: TSTransform,
+    elapsed: f32,
+    duration: f32,
+    /// 上一帧动画设置的变换，与当前变换不同说明用户手动平移或缩放了
+    current: TSTransform,
+}
+
+impl CameraAnimation {
+    fn new(from: TSTransform, to: TSTransform) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            duration: CAMERA_ANIMATION_DURATION,
+            current: from,
+        }
+    }
+
+    fn transform_at(&self, t: f32, screen_center: egui::Pos2) -> TSTransform {
+        // 缓入缓出
+        let eased = t * t * (3.0 - 2.0 * t);
+        let center_of =
+            |transform: &TSTransform| transform.inverse().mul_pos(screen_center).to_vec2();
+        let center = center_of(&self.from) + (center_of(&self.to) - center_of(&self.from)) * eased;
+        let scaling = (self.from.scaling.ln()
+            + (self.to.scaling.ln() - self.from.scaling.ln()) * eased)
+            .exp();
+        TSTransform::new(screen_center.to_vec2() - center * scaling, scaling)
+    }
+}
+
+/// 保存的视角，用于在不同区域之间跳转
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub transform: TSTransform,
+}
+
+/// 演示路径中的一步
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub enum PresentationStep {
+    /// 聚焦到该编号的节点
+    Node(u64),
+    /// 跳转到保存的视角
+    View(CameraBookmark),
+}
+
+/// 缩小视图时的细节层次
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DetailLevel {
+    /// 节点只画成小色块，边只画线
+    Dots,
+    /// 节点画成不带文字的方框，边不画箭头和标签
+    Boxes,
+    /// 完整绘制
+    Full,
+}
+
+/// 切换细节层次的缩放阈值
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct LodSettings {
+    /// 缩放比例低于它时节点画成方框
+    pub boxes_below: f32,
+    /// 缩放比例低于它时节点画成色块
+    pub dots_below: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            boxes_below: 0.5,
+            dots_below: 0.2,
+        }
+    }
+}
+
+impl LodSettings {
+    pub fn level(&self, scaling: f32) -> DetailLevel {
+        if scaling < self.dots_below {
+            DetailLevel::Dots
+        } else if scaling < self.boxes_below {
+            DetailLevel::Boxes
+        } else {
+            DetailLevel::Full
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct CanvasState {
-    pub offset: egui::Vec2,
-    pub scale: f32,
     // 从画布到屏幕的变换矩阵
     pub transform: TSTransform,
     pub global_node_id: AtomicU64,
     pub global_edge_id: AtomicU64,
+    /// 拖动节点时吸附到可见的网格
+    #[serde(default)]
+    pub snap_to_grid: bool,
+    /// 拖动节点时显示对齐参考线并吸附
+    #[serde(default = "default_true")]
+    pub smart_guides: bool,
+    /// 在画布角落显示小地图
+    #[serde(default = "default_true")]
+    pub show_minimap: bool,
+    /// 缩小视图时简化绘制的阈值
+    #[serde(default)]
+    pub lod: LodSettings,
+    /// 保存的视角，前九个可以用数字键跳转
+    #[serde(default)]
+    pub bookmarks: Vec<CameraBookmark>,
+    /// 演示模式依次经过的节点和视角
+    #[serde(default)]
+    pub presentation: Vec<PresentationStep>,
+    /// 画布在屏幕上的区域，每帧由画布控件更新
+    #[serde(skip, default = "empty_viewport")]
+    pub viewport: egui::Rect,
+    #[serde(skip)]
+    pub camera_animation: Option<CameraAnimation>,
+    /// 书签或演示路径改变时加一，用来判断文档有没有未保存的修改
+    #[serde(skip)]
+    pub revision: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn empty_viewport() -> egui::Rect {
+    egui::Rect::ZERO
 }
 
 impl Default for CanvasState {
     fn default() -> Self {
         Self {
-            offset: egui::Vec2::ZERO,
-            scale: 1.0,
             transform: TSTransform::IDENTITY,
             global_node_id: AtomicU64::new(0),
             global_edge_id: AtomicU64::new(0),
+            snap_to_grid: false,
+            smart_guides: true,
+            show_minimap: true,
+            lod: LodSettings::default(),
+            bookmarks: Vec::new(),
+            presentation: Vec::new(),
+            viewport: empty_viewport(),
+            camera_animation: None,
+            revision: 0,
         }
     }
 }
@@ -33,6 +175,114 @@ impl CanvasState {
         self.global_edge_id.fetch_add(1, Ordering::Relaxed)
     }
 
+    /// 当前缩放比例下的细节层次
+    pub fn detail_level(&self) -> DetailLevel {
+        self.lod.level(self.transform.scaling)
+    }
+
+    /// 把画布变换平滑地移动到 `target`，视口还没有布局时直接跳过去
+    pub fn animate_to(&mut self, target: TSTransform) {
+        if !self.viewport.is_positive() {
+            self.transform = target;
+            self.camera_animation = None;
+            return;
+        }
+        self.camera_animation = Some(CameraAnimation::new(self.transform, target));
+    }
+
+    /// 让画布上的 `canvas_rect` 完整显示在视口中间的变换
+    pub fn fit_transform(&self, canvas_rect: egui::Rect) -> TSTransform {
+        let available = self.viewport.shrink(FIT_MARGIN);
+        let size = canvas_rect.size().max(egui::Vec2::splat(1.0));
+        let scaling = (available.width() / size.x)
+            .min(available.height() / size.y)
+            .clamp(0.1, MAX_FIT_SCALING);
+        TSTransform::new(
+            self.viewport.center().to_vec2() - canvas_rect.center().to_vec2() * scaling,
+            scaling,
+        )
+    }
+
+    /// 缩放并平移到完整显示 `canvas_rect`
+    pub fn zoom_to_rect(&mut self, canvas_rect: egui::Rect) {
+        self.animate_to(self.fit_transform(canvas_rect));
+    }
+
+    /// 把画布上的点移到视口中心，缩放比例不小于 `min_scaling`
+    pub fn fly_to(&mut self, canvas_pos: egui::Pos2, min_scaling: f32) {
+        let scaling = self.transform.scaling.max(min_scaling);
+        self.animate_to(TSTransform::new(
+            self.viewport.center().to_vec2() - canvas_pos.to_vec2() * scaling,
+            scaling,
+        ));
+    }
+
+    /// 把当前视角保存为书签
+    pub fn add_bookmark(&mut self, name: String) {
+        let transform = self.target_transform();
+        self.bookmarks.push(CameraBookmark { name, transform });
+        self.mark_changed();
+    }
+
+    /// 记录书签或演示路径的修改
+    pub fn mark_changed(&mut self) {
+        self.revision += 1;
+    }
+
+    /// 当前视角，动画进行中时取动画的终点
+    pub fn target_transform(&self) -> TSTransform {
+        self.camera_animation
+            .as_ref()
+            .map_or(self.transform, |animation| animation.to)
+    }
+
+    /// 把当前视角加到演示路径的末尾
+    pub fn add_presentation_view(&mut self) {
+        let name = format!("View {}", self.presentation.len() + 1);
+        let transform = self.target_transform();
+        self.presentation
+            .push(PresentationStep::View(CameraBookmark { name, transform }));
+        self.mark_changed();
+    }
+
+    /// 平滑跳转到第 `index` 个书签，书签不存在时返回 false
+    pub fn go_to_bookmark(&mut self, index: usize) -> bool {
+        let Some(bookmark) = self.bookmarks.get(index) else {
+            return false;
+        };
+        self.animate_to(bookmark.transform);
+        true
+    }
+
+    /// 推进相机动画，返回动画是否仍在进行
+    pub fn step_camera_animation(&mut self, dt: f32) -> bool {
+        let Some(animation) = self.camera_animation.as_mut() else {
+            return false;
+        };
+        // 用户在动画过程中手动平移或缩放时停止动画
+        if self.transform != animation.current {
+            self.camera_animation = None;
+            return false;
+        }
+        animation.elapsed = (animation.elapsed + dt).min(animation.duration);
+        let t = if animation.duration <= 0.0 {
+            1.0
+        } else {
+            animation.elapsed / animation.duration
+        };
+        let transform = if t >= 1.0 {
+            animation.to
+        } else {
+            animation.transform_at(t, self.viewport.center())
+        };
+        animation.current = transform;
+        self.transform = transform;
+        if t >= 1.0 {
+            self.camera_animation = None;
+        }
+        t < 1.0
+    }
+
     /// 将"画布坐标"转换到"屏幕坐标"
     pub fn to_screen(&self, canvas_pos: egui::Pos2) -> egui::Pos2 {
         // 假设：先缩放，再平移
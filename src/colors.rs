@@ -19,3 +19,43 @@ pub fn node_background(theme: egui::Theme) -> egui::Color32 {
         egui::Color32::from_rgba_premultiplied(70, 70, 70, 200)
     }
 }
+
+pub fn edge_stroke(_theme: egui::Theme) -> egui::Color32 {
+    egui::Color32::GRAY
+}
+
+pub fn edge_selected(theme: egui::Theme) -> egui::Color32 {
+    node_border_selected(theme)
+}
+
+pub fn edge_label_background(theme: egui::Theme) -> egui::Color32 {
+    if theme == egui::Theme::Light {
+        egui::Color32::from_rgb(235, 235, 235)
+    } else {
+        egui::Color32::from_rgb(45, 45, 45)
+    }
+}
+
+pub fn edge_label_text(theme: egui::Theme) -> egui::Color32 {
+    if theme == egui::Theme::Light {
+        egui::Color32::from_rgb(40, 40, 40)
+    } else {
+        egui::Color32::from_rgb(220, 220, 220)
+    }
+}
+
+pub fn minimap_background(theme: egui::Theme) -> egui::Color32 {
+    if theme == egui::Theme::Light {
+        egui::Color32::from_rgba_unmultiplied(245, 245, 245, 220)
+    } else {
+        egui::Color32::from_rgba_unmultiplied(30, 30, 30, 220)
+    }
+}
+
+pub fn minimap_viewport(theme: egui::Theme) -> egui::Color32 {
+    if theme == egui::Theme::Light {
+        egui::Color32::from_rgb(19, 90, 155)
+    } else {
+        egui::Color32::from_rgb(66, 144, 218)
+    }
+}
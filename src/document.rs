@@ -0,0 +1,221 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::resource::{CanvasStateResource, GraphResource};
+
+/// 当前的文件格式版本
+///
+/// 修改了图或画布的数据结构时把它加一，并在 [`MIGRATIONS`] 末尾加上从上一个版本升级的函数。
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 把文档从版本 `i` 升级到版本 `i + 1` 的函数，下标就是起始版本
+type Migration = fn(Value) -> Result<Value, DocumentError>;
+
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [migrate_v0_to_v1];
+
+/// 保存到 .cnt 文件中的文档：图和画布状态，以及文件格式版本
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct Document {
+    pub version: u32,
+    pub graph: GraphResource,
+    pub canvas: CanvasStateResource,
+}
+
+/// 读取文档失败的原因
+#[derive(Debug)]
+pub enum DocumentError {
+    /// 文件不是合法的 JSON，或者内容与文档结构不符
+    Parse(serde_json::Error),
+    /// 文件由更新版本的程序保存，无法安全地打开
+    NewerVersion { found: u64, supported: u32 },
+    /// 文件不是 Cognitheon 文档，或者旧版本的数据缺少必要的部分
+    Invalid(String),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "the file could not be read: {e}"),
+            Self::NewerVersion { found, supported } => write!(
+                f,
+                "the file was saved by a newer version of Cognitheon (format {found}, \
+                 this version supports up to {supported}). Please update Cognitheon to open it"
+            ),
+            Self::Invalid(message) => {
+                write!(f, "the file is not a valid Cognitheon document: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl Document {
+    pub fn new(graph: GraphResource, canvas: CanvasStateResource) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            graph,
+            canvas,
+        }
+    }
+
+    /// 复制当前的图和画布状态，保存时在后台序列化副本，不阻塞界面
+    pub fn snapshot(
+        graph: &GraphResource,
+        canvas: &CanvasStateResource,
+    ) -> Result<Self, DocumentError> {
+        let graph = graph.read_resource(|graph| graph.clone_document());
+        // 画布状态很小，又包含原子计数器，直接通过序列化复制
+        let canvas = canvas.read_resource(|canvas| serde_json::to_value(canvas))?;
+        Ok(Self::new(
+            GraphResource::new(graph),
+            serde_json::from_value(canvas)?,
+        ))
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// 读取任意旧版本的文档，逐个版本升级到当前格式
+    pub fn from_json(data: &[u8]) -> Result<Self, DocumentError> {
+        Self::from_value(serde_json::from_slice(data)?)
+    }
+
+    pub fn from_value(mut value: Value) -> Result<Self, DocumentError> {
+        let version = document_version(&value)?;
+        if version > FORMAT_VERSION as u64 {
+            return Err(DocumentError::NewerVersion {
+                found: version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        for migration in &MIGRATIONS[version as usize..] {
+            value = migration(value)?;
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// 文档的格式版本，没有版本号的是版本 0：直接序列化整个应用状态的旧格式
+fn document_version(value: &Value) -> Result<u64, DocumentError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| DocumentError::Invalid("expected a JSON object".to_owned()))?;
+    match object.get("version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| DocumentError::Invalid(format!("bad format version {version}"))),
+        None => Ok(0),
+    }
+}
+
+/// 版本 0 保存的是整个应用状态，只取出其中的图和画布，丢掉示例字段和界面设置
+fn migrate_v0_to_v1(mut value: Value) -> Result<Value, DocumentError> {
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| DocumentError::Invalid("expected a JSON object".to_owned()))?;
+    let graph = object
+        .remove("graph_resource")
+        .ok_or_else(|| DocumentError::Invalid("missing graph".to_owned()))?;
+    let mut canvas = object
+        .remove("canvas_resource")
+        .ok_or_else(|| DocumentError::Invalid("missing canvas state".to_owned()))?;
+    // 早期画布的平移和缩放已经由 transform 取代
+    if let Some(canvas) = canvas.as_object_mut() {
+        canvas.remove("offset");
+        canvas.remove("scale");
+    }
+    Ok(serde_json::json!({
+        "version": 1,
+        "graph": graph,
+        "canvas": canvas,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::graph::graph_impl::Graph;
+    use crate::graph::node::Node;
+
+    fn sample_graph() -> GraphResource {
+        let mut graph = Graph::default();
+        graph.add_node(Node {
+            id: 7,
+            position: egui::pos2(10.0, 20.0),
+            text: "root".to_owned(),
+            note: "note".to_owned(),
+            collapsed: false,
+            width: None,
+        });
+        GraphResource::new(graph)
+    }
+
+    fn node_texts(document: &Document) -> Vec<(u64, String)> {
+        document.graph.read_resource(|graph| {
+            graph
+                .graph
+                .node_weights()
+                .map(|node| (node.id, node.text.clone()))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn loads_v0_app_state() {
+        let mut canvas = serde_json::to_value(CanvasStateResource::default()).unwrap();
+        canvas["offset"] = json!([0.0, 0.0]);
+        canvas["scale"] = json!(1.0);
+        let legacy = json!({
+            "label": "Hello World!",
+            "show_note_panel": true,
+            "graph_resource": serde_json::to_value(sample_graph()).unwrap(),
+            "canvas_resource": canvas,
+        });
+
+        let document = Document::from_json(legacy.to_string().as_bytes()).unwrap();
+        assert_eq!(document.version, FORMAT_VERSION);
+        assert_eq!(node_texts(&document), vec![(7, "root".to_owned())]);
+    }
+
+    #[test]
+    fn v0_without_graph_is_invalid() {
+        let legacy = json!({ "label": "Hello World!" });
+        assert!(matches!(
+            Document::from_json(legacy.to_string().as_bytes()),
+            Err(DocumentError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_newer_version() {
+        let newer = json!({ "version": FORMAT_VERSION + 1, "graph": {}, "canvas": {} });
+        match Document::from_json(newer.to_string().as_bytes()) {
+            Err(DocumentError::NewerVersion { found, supported }) => {
+                assert_eq!(found, FORMAT_VERSION as u64 + 1);
+                assert_eq!(supported, FORMAT_VERSION);
+            }
+            other => panic!("expected NewerVersion, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trips_current_version() {
+        let document = Document::new(sample_graph(), CanvasStateResource::default());
+        let json = document.to_json().unwrap();
+        let loaded = Document::from_json(json.as_bytes()).unwrap();
+        assert_eq!(loaded.version, FORMAT_VERSION);
+        assert_eq!(node_texts(&loaded), node_texts(&document));
+        assert_eq!(json, loaded.to_json().unwrap());
+    }
+}
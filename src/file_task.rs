@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+use rfd::AsyncFileDialog;
+use tokio::runtime::Runtime;
+
+use crate::document::Document;
+
+/// 后台文件操作的种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileTaskKind {
+    Save,
+    Load,
+}
+
+/// 文件操作的结果
+#[derive(Debug)]
+pub enum FileTaskResult {
+    Saved(PathBuf),
+    Loaded(PathBuf, Document),
+    /// 用户关闭了文件对话框，什么也不做
+    Cancelled,
+    /// 读写或解析失败，内容是给用户看的错误信息
+    Failed(String),
+}
+
+/// 后台任务发回界面的消息
+enum FileTaskEvent {
+    Progress(&'static str),
+    Finished(FileTaskResult),
+}
+
+/// 在 tokio 运行时上执行的保存或打开操作，界面每帧轮询它的进度和结果
+#[derive(Debug)]
+pub struct FileTask {
+    pub kind: FileTaskKind,
+    /// 最近一次报告的进度
+    pub progress: &'static str,
+    receiver: Receiver<FileTaskEvent>,
+}
+
+/// 向界面报告进度，并唤醒界面重绘
+struct Reporter {
+    sender: Sender<FileTaskEvent>,
+    ctx: egui::Context,
+}
+
+impl Reporter {
+    fn progress(&self, progress: &'static str) {
+        // 界面已经丢弃了任务时忽略
+        let _ = self.sender.send(FileTaskEvent::Progress(progress));
+        self.ctx.request_repaint();
+    }
+
+    fn finish(&self, result: FileTaskResult) {
+        let _ = self.sender.send(FileTaskEvent::Finished(result));
+        self.ctx.request_repaint();
+    }
+}
+
+fn file_dialog() -> AsyncFileDialog {
+    AsyncFileDialog::new()
+        .add_filter("Cognitheon", &["cnt"])
+        .set_directory("~")
+}
+
+impl FileTask {
+    fn spawn<F>(
+        kind: FileTaskKind,
+        runtime: &Runtime,
+        ctx: &egui::Context,
+        run: impl FnOnce(Reporter) -> F,
+    ) -> Self
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        runtime.spawn(run(Reporter {
+            sender,
+            ctx: ctx.clone(),
+        }));
+        Self {
+            kind,
+            progress: "Starting",
+            receiver,
+        }
+    }
+
+    /// 在后台序列化文档并写入 `path`，没有路径时先让用户选择保存位置
+    pub fn save(
+        runtime: &Runtime,
+        ctx: &egui::Context,
+        document: Document,
+        path: Option<PathBuf>,
+    ) -> Self {
+        Self::spawn(FileTaskKind::Save, runtime, ctx, |reporter| async move {
+            let path = match path {
+                Some(path) => path,
+                None => match file_dialog().save_file().await {
+                    Some(file) => file.path().to_path_buf(),
+                    None => {
+                        reporter.finish(FileTaskResult::Cancelled);
+                        return;
+                    }
+                },
+            };
+
+            reporter.progress("Serializing");
+            let data = match document.to_json() {
+                Ok(data) => data,
+                Err(e) => {
+                    reporter.finish(FileTaskResult::Failed(format!(
+                        "Could not save {}: {e}",
+                        path.display()
+                    )));
+                    return;
+                }
+            };
+
+            reporter.progress("Writing");
+            let result = match tokio::fs::write(&path, data).await {
+                Ok(()) => FileTaskResult::Saved(path),
+                Err(e) => FileTaskResult::Failed(format!("Could not save {}: {e}", path.display())),
+            };
+            reporter.finish(result);
+        })
+    }
+
+    /// 在后台读取并解析 `path` 处的文档，没有路径时先让用户选择文件
+    pub fn load(runtime: &Runtime, ctx: &egui::Context, path: Option<PathBuf>) -> Self {
+        Self::spawn(FileTaskKind::Load, runtime, ctx, |reporter| async move {
+            let path = match path {
+                Some(path) => path,
+                None => match file_dialog().pick_file().await {
+                    Some(file) => file.path().to_path_buf(),
+                    None => {
+                        reporter.finish(FileTaskResult::Cancelled);
+                        return;
+                    }
+                },
+            };
+
+            reporter.progress("Reading");
+            let data = match tokio::fs::read(&path).await {
+                Ok(data) => data,
+                Err(e) => {
+                    reporter.finish(FileTaskResult::Failed(format!(
+                        "Could not open {}: {e}",
+                        path.display()
+                    )));
+                    return;
+                }
+            };
+
+            reporter.progress("Parsing");
+            let result = match Document::from_json(&data) {
+                Ok(document) => FileTaskResult::Loaded(path, document),
+                Err(e) => FileTaskResult::Failed(format!("Could not open {}: {e}", path.display())),
+            };
+            reporter.finish(result);
+        })
+    }
+
+    /// 取出新的进度，任务结束时返回结果
+    pub fn poll(&mut self) -> Option<FileTaskResult> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(FileTaskEvent::Progress(progress)) => self.progress = progress,
+                Ok(FileTaskEvent::Finished(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(FileTaskResult::Failed(
+                        "The file operation stopped unexpectedly".to_owned(),
+                    ))
+                }
+            }
+        }
+    }
+}
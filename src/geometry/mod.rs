@@ -1,5 +1,9 @@
 use egui::{Pos2, Rect, Vec2};
 
+pub mod orthogonal;
+pub mod quadtree;
+pub mod snap;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IntersectDirection {
     Left,
@@ -182,3 +186,47 @@ pub fn edge_offset_direction(source_canvas_pos: Pos2, target_canvas_pos: Pos2) -
 
     direction.rot90()
 }
+
+// 计算点到线段的最短距离
+pub fn distance_to_segment(pos: Pos2, seg_start: Pos2, seg_end: Pos2) -> f32 {
+    let seg = seg_end - seg_start;
+    let len_sq = seg.length_sq();
+    if len_sq < f32::EPSILON {
+        return (pos - seg_start).length();
+    }
+    let t = ((pos - seg_start).dot(seg) / len_sq).clamp(0.0, 1.0);
+    (pos - (seg_start + seg * t)).length()
+}
+
+// 计算点到折线的最短距离
+pub fn distance_to_polyline(pos: Pos2, points: &[Pos2]) -> f32 {
+    points
+        .windows(2)
+        .map(|seg| distance_to_segment(pos, seg[0], seg[1]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+// 计算折线按弧长的中点以及该处的切线方向
+pub fn polyline_midpoint(points: &[Pos2]) -> Option<(Pos2, Vec2)> {
+    let total: f32 = points
+        .windows(2)
+        .map(|seg| (seg[1] - seg[0]).length())
+        .sum();
+    if total < f32::EPSILON {
+        return None;
+    }
+
+    let mut remaining = total / 2.0;
+    for seg in points.windows(2) {
+        let length = (seg[1] - seg[0]).length();
+        if length < f32::EPSILON {
+            continue;
+        }
+        if remaining <= length {
+            let direction = (seg[1] - seg[0]) / length;
+            return Some((seg[0] + direction * remaining, direction));
+        }
+        remaining -= length;
+    }
+    None
+}
@@ -0,0 +1,284 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use egui::{Pos2, Rect, Vec2};
+
+use super::IntersectDirection;
+
+/// 连线离开节点后先直行的距离
+const STUB_LENGTH: f32 = 20.0;
+/// 绕行时与障碍物保持的距离，需小于 `STUB_LENGTH`
+const OBSTACLE_MARGIN: f32 = 10.0;
+/// 每次拐弯的额外代价，避免出现不必要的折线
+const BEND_PENALTY: f32 = 40.0;
+/// 只考虑首尾连线附近的障碍物
+const SEARCH_MARGIN: f32 = 200.0;
+
+fn direction_vec(direction: IntersectDirection) -> Vec2 {
+    match direction {
+        IntersectDirection::Left => Vec2::new(-1.0, 0.0),
+        IntersectDirection::Right => Vec2::new(1.0, 0.0),
+        IntersectDirection::Top => Vec2::new(0.0, -1.0),
+        IntersectDirection::Bottom => Vec2::new(0.0, 1.0),
+    }
+}
+
+/// 计算一条只包含水平和竖直线段的折线
+///
+/// `source_dir` / `target_dir` 为连线在首尾节点上所在的边，`obstacles` 为需要绕开的节点矩形
+/// （可以包含首尾节点本身）。找不到可行路径时退化为简单的肘形连线。
+pub fn route_orthogonal(
+    source: Pos2,
+    source_dir: IntersectDirection,
+    target: Pos2,
+    target_dir: IntersectDirection,
+    obstacles: &[Rect],
+) -> Vec<Pos2> {
+    let source_vec = direction_vec(source_dir);
+    let target_vec = direction_vec(target_dir);
+    let source_stub = source + source_vec * STUB_LENGTH;
+    let target_stub = target + target_vec * STUB_LENGTH;
+
+    let search_rect = orthogonal_search_rect(source, target);
+    let obstacles = obstacles
+        .iter()
+        .filter(|rect| rect.intersects(search_rect))
+        .map(|rect| rect.expand(OBSTACLE_MARGIN))
+        .collect::<Vec<_>>();
+
+    let path = search_grid(
+        source_stub,
+        source_vec,
+        target_stub,
+        -target_vec,
+        &obstacles,
+    )
+    .unwrap_or_else(|| elbow(source_stub, source_vec, target_stub));
+
+    let mut points = Vec::with_capacity(path.len() + 2);
+    points.push(source);
+    points.extend(path);
+    points.push(target);
+    simplify(points)
+}
+
+/// 在首尾端点之间路由时会考虑的范围，范围外的障碍物不影响结果
+pub fn orthogonal_search_rect(source: Pos2, target: Pos2) -> Rect {
+    Rect::from_two_pos(source, target).expand(STUB_LENGTH + SEARCH_MARGIN)
+}
+
+/// 没有可行路径时使用的肘形连线
+fn elbow(source_stub: Pos2, source_vec: Vec2, target_stub: Pos2) -> Vec<Pos2> {
+    if source_vec.x != 0.0 {
+        let mid_x = (source_stub.x + target_stub.x) / 2.0;
+        vec![
+            source_stub,
+            Pos2::new(mid_x, source_stub.y),
+            Pos2::new(mid_x, target_stub.y),
+            target_stub,
+        ]
+    } else {
+        let mid_y = (source_stub.y + target_stub.y) / 2.0;
+        vec![
+            source_stub,
+            Pos2::new(source_stub.x, mid_y),
+            Pos2::new(target_stub.x, mid_y),
+            target_stub,
+        ]
+    }
+}
+
+/// 去掉重复点和共线的中间点
+fn simplify(points: Vec<Pos2>) -> Vec<Pos2> {
+    let mut result: Vec<Pos2> = Vec::with_capacity(points.len());
+    for point in points {
+        if result
+            .last()
+            .is_some_and(|last| (point - *last).length_sq() < 1e-4)
+        {
+            continue;
+        }
+        if result.len() >= 2 {
+            let a = result[result.len() - 2];
+            let b = result[result.len() - 1];
+            let collinear = ((a.x - b.x).abs() < 1e-3 && (b.x - point.x).abs() < 1e-3)
+                || ((a.y - b.y).abs() < 1e-3 && (b.y - point.y).abs() < 1e-3);
+            if collinear {
+                result.pop();
+            }
+        }
+        result.push(point);
+    }
+    result
+}
+
+/// 四个移动方向：右、左、下、上
+const DIRECTIONS: [Vec2; 4] = [
+    Vec2::new(1.0, 0.0),
+    Vec2::new(-1.0, 0.0),
+    Vec2::new(0.0, 1.0),
+    Vec2::new(0.0, -1.0),
+];
+
+fn direction_index(v: Vec2) -> usize {
+    DIRECTIONS.iter().position(|d| d.dot(v) > 0.5).unwrap_or(0)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct SearchState {
+    cost: f32,
+    node: usize,
+    direction: usize,
+}
+
+impl Eq for SearchState {}
+
+impl Ord for SearchState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap 是大顶堆，代价小的优先
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 在障碍物边界生成的稀疏网格上搜索拐弯最少、长度最短的路径
+fn search_grid(
+    start: Pos2,
+    start_vec: Vec2,
+    end: Pos2,
+    end_vec: Vec2,
+    obstacles: &[Rect],
+) -> Option<Vec<Pos2>> {
+    let inside = |p: Pos2| {
+        obstacles
+            .iter()
+            .any(|r| p.x > r.min.x && p.x < r.max.x && p.y > r.min.y && p.y < r.max.y)
+    };
+    if inside(start) || inside(end) {
+        return None;
+    }
+
+    let mut xs = vec![start.x, end.x, (start.x + end.x) / 2.0];
+    let mut ys = vec![start.y, end.y, (start.y + end.y) / 2.0];
+    for rect in obstacles {
+        xs.extend([rect.min.x, rect.max.x]);
+        ys.extend([rect.min.y, rect.max.y]);
+    }
+    for values in [&mut xs, &mut ys] {
+        values.sort_by(f32::total_cmp);
+        values.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+    }
+
+    let ny = ys.len();
+    let position = |node: usize| Pos2::new(xs[node / ny], ys[node % ny]);
+    let find = |values: &[f32], v: f32| values.iter().position(|x| (x - v).abs() < 1e-3);
+    let start_node = find(&xs, start.x)? * ny + find(&ys, start.y)?;
+    let end_node = find(&xs, end.x)? * ny + find(&ys, end.y)?;
+
+    // 线段是否穿过障碍物内部，贴着边界走是允许的
+    let blocked = |a: Pos2, b: Pos2| {
+        let (min, max) = (a.min(b), a.max(b));
+        obstacles.iter().any(|r| {
+            if (a.y - b.y).abs() < 1e-3 {
+                a.y > r.min.y && a.y < r.max.y && min.x < r.max.x && max.x > r.min.x
+            } else {
+                a.x > r.min.x && a.x < r.max.x && min.y < r.max.y && max.y > r.min.y
+            }
+        })
+    };
+
+    let neighbor = |node: usize, direction: usize| -> Option<usize> {
+        let (ix, iy) = (node / ny, node % ny);
+        match direction {
+            0 => (ix + 1 < xs.len()).then(|| (ix + 1) * ny + iy),
+            1 => ix.checked_sub(1).map(|ix| ix * ny + iy),
+            2 => (iy + 1 < ny).then(|| ix * ny + iy + 1),
+            _ => iy.checked_sub(1).map(|iy| ix * ny + iy),
+        }
+    };
+
+    let state_count = xs.len() * ny * 4;
+    let mut best = vec![f32::INFINITY; state_count];
+    let mut previous = vec![usize::MAX; state_count];
+    let mut heap = BinaryHeap::new();
+
+    let start_direction = direction_index(start_vec);
+    let end_direction = direction_index(end_vec);
+    best[start_node * 4 + start_direction] = 0.0;
+    heap.push(SearchState {
+        cost: 0.0,
+        node: start_node,
+        direction: start_direction,
+    });
+
+    let mut found = None;
+    while let Some(SearchState {
+        cost,
+        node,
+        direction,
+    }) = heap.pop()
+    {
+        let state = node * 4 + direction;
+        if cost > best[state] {
+            continue;
+        }
+        if node == end_node {
+            // 最后一段需要朝向目标节点
+            let cost = if direction == end_direction {
+                cost
+            } else {
+                cost + BEND_PENALTY
+            };
+            if found.is_none_or(|(found_cost, _)| cost < found_cost) {
+                found = Some((cost, state));
+            }
+            continue;
+        }
+        if found.is_some_and(|(found_cost, _)| cost >= found_cost) {
+            break;
+        }
+
+        let from = position(node);
+        for (next_direction, next_vec) in DIRECTIONS.iter().enumerate() {
+            // 不允许原路折返
+            if *next_vec == -DIRECTIONS[direction] {
+                continue;
+            }
+            let Some(next_node) = neighbor(node, next_direction) else {
+                continue;
+            };
+            let to = position(next_node);
+            if blocked(from, to) {
+                continue;
+            }
+            let mut next_cost = cost + (to - from).length();
+            if next_direction != direction {
+                next_cost += BEND_PENALTY;
+            }
+            let next_state = next_node * 4 + next_direction;
+            if next_cost < best[next_state] {
+                best[next_state] = next_cost;
+                previous[next_state] = state;
+                heap.push(SearchState {
+                    cost: next_cost,
+                    node: next_node,
+                    direction: next_direction,
+                });
+            }
+        }
+    }
+
+    let (_, mut state) = found?;
+    let mut path = vec![position(state / 4)];
+    while previous[state] != usize::MAX {
+        state = previous[state];
+        path.push(position(state / 4));
+    }
+    path.reverse();
+    Some(path)
+}
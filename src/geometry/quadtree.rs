@@ -0,0 +1,191 @@
+use egui::{Pos2, Rect};
+
+/// 叶子节点最多保存的元素数，超过时继续细分
+const MAX_ITEMS: usize = 16;
+
+/// 最大细分深度，大量元素重叠在一起时停止细分
+const MAX_DEPTH: usize = 12;
+
+/// 保存矩形的四叉树，用于按区域快速查找元素
+///
+/// 跨越多个子区域的元素保存在能完整容纳它的最小节点上。根节点的范围在构建时确定，
+/// 之后插入的超出范围的元素单独保存，数量多了以后由调用方重新构建。
+#[derive(Clone, Debug)]
+pub struct QuadTree<T> {
+    root: QuadNode<T>,
+    /// 超出根节点范围的元素
+    outside: Vec<(Rect, T)>,
+    len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct QuadNode<T> {
+    bounds: Rect,
+    items: Vec<(Rect, T)>,
+    children: Option<Box<[QuadNode<T>; 4]>>,
+}
+
+impl<T> Default for QuadTree<T> {
+    fn default() -> Self {
+        Self {
+            root: QuadNode::new(Rect::NOTHING),
+            outside: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> QuadTree<T> {
+    /// 用一组元素构建四叉树，根节点覆盖所有元素，并留出余量给之后移动的元素
+    pub fn build(items: Vec<(Rect, T)>) -> Self {
+        let bounds = items
+            .iter()
+            .fold(Rect::NOTHING, |bounds, (rect, _)| bounds.union(*rect));
+        let bounds = if bounds.is_finite() {
+            bounds.expand(bounds.size().max_elem() * 0.25)
+        } else {
+            bounds
+        };
+        let mut tree = Self {
+            root: QuadNode::new(bounds),
+            outside: Vec::new(),
+            len: 0,
+        };
+        for (rect, item) in items {
+            tree.insert(rect, item);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, rect: Rect, item: T) {
+        self.len += 1;
+        if self.root.bounds.contains_rect(rect) {
+            self.root.insert(rect, item, 0);
+        } else {
+            self.outside.push((rect, item));
+        }
+    }
+
+    /// 删除以 `rect` 插入的元素，`rect` 必须与插入时相同
+    pub fn remove(&mut self, rect: Rect, item: T) -> bool {
+        let removed = if self.root.bounds.contains_rect(rect) {
+            self.root.remove(rect, item)
+        } else {
+            remove_item(&mut self.outside, rect, item)
+        };
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// 超出范围的元素太多，查询变慢，应当重新构建
+    pub fn needs_rebuild(&self) -> bool {
+        self.outside.len() > MAX_ITEMS.max(self.len / 8)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 与 `rect` 相交的所有元素
+    pub fn query_rect(&self, rect: Rect) -> Vec<(Rect, T)> {
+        let mut result = Vec::new();
+        let hit = |bounds: Rect| bounds.intersects(rect);
+        self.root.query(&hit, &mut result);
+        result.extend(self.outside.iter().filter(|(rect, _)| hit(*rect)).copied());
+        result
+    }
+
+    /// 包含 `pos` 的所有元素
+    pub fn query_point(&self, pos: Pos2) -> Vec<(Rect, T)> {
+        let mut result = Vec::new();
+        let hit = |bounds: Rect| bounds.contains(pos);
+        self.root.query(&hit, &mut result);
+        result.extend(self.outside.iter().filter(|(rect, _)| hit(*rect)).copied());
+        result
+    }
+}
+
+impl<T> QuadNode<T> {
+    fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> QuadNode<T> {
+    fn insert(&mut self, rect: Rect, item: T, depth: usize) {
+        if let Some(children) = self.children.as_mut() {
+            if let Some(child) = children.iter_mut().find(|c| c.bounds.contains_rect(rect)) {
+                child.insert(rect, item, depth + 1);
+                return;
+            }
+            self.items.push((rect, item));
+            return;
+        }
+
+        self.items.push((rect, item));
+        if self.items.len() > MAX_ITEMS && depth < MAX_DEPTH {
+            self.split(depth);
+        }
+    }
+
+    /// 沿插入时的路径找到元素并删除
+    fn remove(&mut self, rect: Rect, item: T) -> bool {
+        if let Some(children) = self.children.as_mut() {
+            if let Some(child) = children.iter_mut().find(|c| c.bounds.contains_rect(rect)) {
+                return child.remove(rect, item);
+            }
+        }
+        remove_item(&mut self.items, rect, item)
+    }
+
+    fn split(&mut self, depth: usize) {
+        let (min, center, max) = (self.bounds.min, self.bounds.center(), self.bounds.max);
+        self.children = Some(Box::new([
+            QuadNode::new(Rect::from_min_max(min, center)),
+            QuadNode::new(Rect::from_min_max(
+                Pos2::new(center.x, min.y),
+                Pos2::new(max.x, center.y),
+            )),
+            QuadNode::new(Rect::from_min_max(
+                Pos2::new(min.x, center.y),
+                Pos2::new(center.x, max.y),
+            )),
+            QuadNode::new(Rect::from_min_max(center, max)),
+        ]));
+        for (rect, item) in std::mem::take(&mut self.items) {
+            self.insert(rect, item, depth);
+        }
+    }
+
+    fn query(&self, hit: &impl Fn(Rect) -> bool, result: &mut Vec<(Rect, T)>) {
+        if !hit(self.bounds) {
+            return;
+        }
+        result.extend(self.items.iter().filter(|(rect, _)| hit(*rect)).copied());
+        if let Some(children) = self.children.as_ref() {
+            for child in children.iter() {
+                child.query(hit, result);
+            }
+        }
+    }
+}
+
+fn remove_item<T: PartialEq>(items: &mut Vec<(Rect, T)>, rect: Rect, item: T) -> bool {
+    match items.iter().position(|(r, i)| *r == rect && *i == item) {
+        Some(position) => {
+            items.swap_remove(position);
+            true
+        }
+        None => false,
+    }
+}
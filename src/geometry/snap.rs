@@ -0,0 +1,169 @@
+use egui::{Pos2, Rect, Vec2};
+
+/// 吸附的结果：移动量的修正和需要显示的参考线（画布坐标）
+#[derive(Debug, Clone, Default)]
+pub struct Snap {
+    pub offset: Vec2,
+    pub guides: Vec<[Pos2; 2]>,
+}
+
+/// 一个方向上的吸附候选
+struct AxisSnap {
+    offset: f32,
+    guides: Vec<[Pos2; 2]>,
+}
+
+/// 吸附正在拖动的矩形
+///
+/// 每个方向上取最近的对齐位置（附近节点的边和中心）或等间距位置，
+/// 都没有时吸附到 `grid` 网格。`threshold` 是画布坐标中的吸附距离。
+pub fn snap_rect(
+    moving: Rect,
+    others: &[Rect],
+    threshold: f32,
+    guides: bool,
+    grid: Option<f32>,
+) -> Snap {
+    let transpose = |rect: &Rect| {
+        Rect::from_min_max(
+            Pos2::new(rect.min.y, rect.min.x),
+            Pos2::new(rect.max.y, rect.max.x),
+        )
+    };
+    let transposed: Vec<Rect> = others.iter().map(transpose).collect();
+
+    let snap_x = guides
+        .then(|| snap_axis(moving, others, threshold))
+        .flatten();
+    let snap_y = guides
+        .then(|| snap_axis(transpose(&moving), &transposed, threshold))
+        .flatten();
+
+    let to_grid = |value: f32| grid.map_or(0.0, |grid| (value / grid).round() * grid - value);
+    let offset = Vec2::new(
+        snap_x.map_or(to_grid(moving.min.x), |s| s.offset),
+        snap_y.map_or(to_grid(moving.min.y), |s| s.offset),
+    );
+
+    // 参考线按吸附后的位置重新计算，两个方向的线都延伸到最终的矩形
+    let mut guides_out = Vec::new();
+    if guides {
+        let snapped = moving.translate(offset);
+        const EXACT: f32 = 0.5;
+        if let Some(snap_x) = snap_axis(snapped, others, EXACT) {
+            guides_out.extend(snap_x.guides);
+        }
+        if let Some(snap_y) = snap_axis(transpose(&snapped), &transposed, EXACT) {
+            guides_out.extend(
+                snap_y
+                    .guides
+                    .into_iter()
+                    .map(|[a, b]| [Pos2::new(a.y, a.x), Pos2::new(b.y, b.x)]),
+            );
+        }
+    }
+    Snap {
+        offset,
+        guides: guides_out,
+    }
+}
+
+/// 沿 x 方向寻找最近的对齐或等间距位置，y 方向通过转置矩形复用
+fn snap_axis(moving: Rect, others: &[Rect], threshold: f32) -> Option<AxisSnap> {
+    let xs = |rect: &Rect| [rect.min.x, rect.center().x, rect.max.x];
+
+    // 对齐：左、中、右任意一条线与其他节点的线重合
+    let mut best: Option<f32> = None;
+    for other in others {
+        for target in xs(other) {
+            for source in xs(&moving) {
+                let offset = target - source;
+                if offset.abs() <= threshold && best.is_none_or(|b| offset.abs() < b.abs()) {
+                    best = Some(offset);
+                }
+            }
+        }
+    }
+
+    // 等间距：只考虑与拖动矩形在同一行的节点
+    let mut row: Vec<Rect> = others
+        .iter()
+        .filter(|other| other.min.y < moving.max.y && other.max.y > moving.min.y)
+        .copied()
+        .collect();
+    row.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+    let mut spacing: Option<(f32, Vec<[Pos2; 2]>)> = None;
+    let mut consider = |offset: f32, gaps: Vec<[Pos2; 2]>| {
+        if offset.abs() <= threshold
+            && best.is_none_or(|b| offset.abs() < b.abs())
+            && spacing.as_ref().is_none_or(|(s, _)| offset.abs() < s.abs())
+        {
+            spacing = Some((offset, gaps));
+        }
+    };
+    let gap_line = |from: f32, to: f32, a: &Rect, b: &Rect| {
+        let y = (a.center().y + b.center().y) / 2.0;
+        [Pos2::new(from, y), Pos2::new(to, y)]
+    };
+    for pair in row.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let gap = b.min.x - a.max.x;
+        if gap <= 0.0 {
+            continue;
+        }
+        // 放在这一对的右边或左边，间距相同
+        let right = b.max.x + gap - moving.min.x;
+        let moved = moving.translate(Vec2::new(right, 0.0));
+        consider(
+            right,
+            vec![
+                gap_line(a.max.x, b.min.x, &a, &b),
+                gap_line(b.max.x, moved.min.x, &b, &moved),
+            ],
+        );
+        let left = a.min.x - gap - moving.max.x;
+        let moved = moving.translate(Vec2::new(left, 0.0));
+        consider(
+            left,
+            vec![
+                gap_line(moved.max.x, a.min.x, &moved, &a),
+                gap_line(a.max.x, b.min.x, &a, &b),
+            ],
+        );
+        // 放在这一对中间，两侧间距相同
+        let free = gap - moving.width();
+        if free > 0.0 {
+            let middle = a.max.x + free / 2.0 - moving.min.x;
+            let moved = moving.translate(Vec2::new(middle, 0.0));
+            consider(
+                middle,
+                vec![
+                    gap_line(a.max.x, moved.min.x, &a, &moved),
+                    gap_line(moved.max.x, b.min.x, &moved, &b),
+                ],
+            );
+        }
+    }
+
+    if let Some((offset, guides)) = spacing {
+        return Some(AxisSnap { offset, guides });
+    }
+
+    let offset = best?;
+    let moved = moving.translate(Vec2::new(offset, 0.0));
+    // 每条对齐的线从拖动的矩形画到所有对齐的节点
+    let mut guides = Vec::new();
+    for x in xs(&moved) {
+        let aligned: Vec<&Rect> = others
+            .iter()
+            .filter(|other| xs(other).iter().any(|o| (o - x).abs() < 0.5))
+            .collect();
+        if aligned.is_empty() {
+            continue;
+        }
+        let top = aligned.iter().map(|r| r.min.y).fold(moved.min.y, f32::min);
+        let bottom = aligned.iter().map(|r| r.max.y).fold(moved.max.y, f32::max);
+        guides.push([Pos2::new(x, top), Pos2::new(x, bottom)]);
+    }
+    Some(AxisSnap { offset, guides })
+}
@@ -54,6 +54,14 @@ impl BezierAnchor {
         self
     }
 
+    // 移动锚点，控制柄保持相对偏移
+    pub fn move_to(&mut self, canvas_pos: egui::Pos2) {
+        let delta = canvas_pos - self.canvas_pos;
+        self.canvas_pos = canvas_pos;
+        self.handle_in_canvas_pos += delta;
+        self.handle_out_canvas_pos += delta;
+    }
+
     // 强制设为平滑锚点，并更新控制柄为对称状态
     pub fn set_smooth(&mut self) {
         self.is_smooth = true;
@@ -65,9 +73,31 @@ impl BezierAnchor {
         self.is_smooth = false;
     }
 
-    // 强制更新控制柄为对称状态，保持平滑
+    // 按进入控制柄的方向更新退出控制柄，保持平滑。两个控制柄各自保留长度，
+    // 细分得到的长度不等的控制柄在拖动时不会跳变
     pub fn enforce_smooth(&mut self) {
-        let in_vec = self.canvas_pos - self.handle_in_canvas_pos;
-        self.handle_out_canvas_pos = self.canvas_pos + in_vec;
+        self.handle_out_canvas_pos = mirror_handle(
+            self.canvas_pos,
+            self.handle_in_canvas_pos,
+            self.handle_out_canvas_pos,
+        );
+    }
+
+    // 按退出控制柄的方向更新进入控制柄，保持平滑
+    pub fn enforce_smooth_from_out(&mut self) {
+        self.handle_in_canvas_pos = mirror_handle(
+            self.canvas_pos,
+            self.handle_out_canvas_pos,
+            self.handle_in_canvas_pos,
+        );
+    }
+}
+
+/// 把 `other` 转到 `handle` 关于锚点的反方向上，长度不变
+fn mirror_handle(anchor: egui::Pos2, handle: egui::Pos2, other: egui::Pos2) -> egui::Pos2 {
+    let direction = (anchor - handle).normalized();
+    if direction == Vec2::ZERO {
+        return other;
     }
+    anchor + direction * (other - anchor).length()
 }
@@ -0,0 +1,328 @@
+use egui::{Pos2, Vec2};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::EdgeRef;
+
+use super::edge::Edge;
+use super::node::Node;
+
+/// 可撤销的图编辑命令
+///
+/// StableGraph 的空闲槽位是后进先出的，只要严格按相反顺序撤销，
+/// 重新插入的节点和边就会拿回原来的索引，选择和输入状态中保存的索引依然有效。
+#[derive(Clone, Debug)]
+pub enum GraphCommand {
+    /// 添加节点，`index` 在首次执行后写入
+    AddNode {
+        node: Node,
+        index: Option<NodeIndex>,
+    },
+
+    /// 删除节点，同时记录被一并删除的边
+    RemoveNode {
+        index: NodeIndex,
+        node: Option<Node>,
+        edges: Vec<(EdgeIndex, Edge)>,
+    },
+
+    /// 添加边，`index` 在首次执行后写入
+    AddEdge {
+        edge: Edge,
+        index: Option<EdgeIndex>,
+    },
+
+    /// 删除边
+    RemoveEdge {
+        index: EdgeIndex,
+        edge: Option<Edge>,
+    },
+
+    /// 整体平移一组节点
+    MoveNodes { nodes: Vec<NodeIndex>, delta: Vec2 },
+
+    /// 把节点放到指定位置（旧位置，新位置），用于自动布局
+    SetNodePositions {
+        positions: Vec<(NodeIndex, Pos2, Pos2)>,
+    },
+
+    /// 修改节点文本
+    SetNodeText {
+        index: NodeIndex,
+        old_text: String,
+        new_text: String,
+    },
+
+    /// 修改节点备注
+    SetNodeNote {
+        index: NodeIndex,
+        old_note: String,
+        new_note: String,
+    },
+
+    /// 折叠或展开节点的子树
+    SetNodeCollapsed { index: NodeIndex, collapsed: bool },
+
+    /// 修改节点的固定宽度（旧宽度，新宽度）
+    SetNodeWidths {
+        widths: Vec<(NodeIndex, Option<f32>, Option<f32>)>,
+    },
+
+    /// 修改边的属性，例如贝塞尔锚点、控制柄和路由方式
+    UpdateEdge {
+        index: EdgeIndex,
+        old_edge: Edge,
+        new_edge: Edge,
+    },
+
+    /// 作为一个撤销步骤执行的一组命令
+    Batch(Vec<GraphCommand>),
+}
+
+impl GraphCommand {
+    pub fn add_node(node: Node) -> Self {
+        GraphCommand::AddNode { node, index: None }
+    }
+
+    pub fn remove_node(index: NodeIndex) -> Self {
+        GraphCommand::RemoveNode {
+            index,
+            node: None,
+            edges: Vec::new(),
+        }
+    }
+
+    pub fn add_edge(edge: Edge) -> Self {
+        GraphCommand::AddEdge { edge, index: None }
+    }
+
+    pub fn remove_edge(index: EdgeIndex) -> Self {
+        GraphCommand::RemoveEdge { index, edge: None }
+    }
+
+    /// 执行（或重做）命令
+    pub fn apply(&mut self, graph: &mut StableGraph<Node, Edge>) {
+        match self {
+            GraphCommand::AddNode { node, index } => {
+                let new_index = graph.add_node(node.clone());
+                debug_assert!(index.is_none_or(|index| index == new_index));
+                *index = Some(new_index);
+            }
+            GraphCommand::RemoveNode { index, node, edges } => {
+                // 先逐条删除相连的边，保证撤销时能按相反顺序还原
+                let edge_indices = graph
+                    .edges_directed(*index, petgraph::Direction::Outgoing)
+                    .chain(graph.edges_directed(*index, petgraph::Direction::Incoming))
+                    .map(|edge| edge.id())
+                    .collect::<Vec<EdgeIndex>>();
+
+                edges.clear();
+                for edge_index in edge_indices {
+                    if let Some(edge) = graph.remove_edge(edge_index) {
+                        edges.push((edge_index, edge));
+                    }
+                }
+                *node = graph.remove_node(*index);
+            }
+            GraphCommand::AddEdge { edge, index } => {
+                let new_index = graph.add_edge(edge.source, edge.target, edge.clone());
+                debug_assert!(index.is_none_or(|index| index == new_index));
+                *index = Some(new_index);
+            }
+            GraphCommand::RemoveEdge { index, edge } => {
+                *edge = graph.remove_edge(*index);
+            }
+            GraphCommand::MoveNodes { nodes, delta } => {
+                for &node_index in nodes.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.position += *delta;
+                    }
+                }
+            }
+            GraphCommand::SetNodePositions { positions } => {
+                for &(node_index, _, new_position) in positions.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.position = new_position;
+                    }
+                }
+            }
+            GraphCommand::SetNodeText {
+                index, new_text, ..
+            } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.text = new_text.clone();
+                }
+            }
+            GraphCommand::SetNodeNote {
+                index, new_note, ..
+            } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.note = new_note.clone();
+                }
+            }
+            GraphCommand::SetNodeCollapsed { index, collapsed } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.collapsed = *collapsed;
+                }
+            }
+            GraphCommand::SetNodeWidths { widths } => {
+                for &(node_index, _, new_width) in widths.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.width = new_width;
+                    }
+                }
+            }
+            GraphCommand::UpdateEdge {
+                index, new_edge, ..
+            } => {
+                if let Some(edge) = graph.edge_weight_mut(*index) {
+                    *edge = new_edge.clone();
+                }
+            }
+            GraphCommand::Batch(commands) => {
+                for command in commands.iter_mut() {
+                    command.apply(graph);
+                }
+            }
+        }
+    }
+
+    /// 撤销命令
+    pub fn revert(&mut self, graph: &mut StableGraph<Node, Edge>) {
+        match self {
+            GraphCommand::AddNode { index, .. } => {
+                if let Some(index) = index {
+                    graph.remove_node(*index);
+                }
+            }
+            GraphCommand::RemoveNode { index, node, edges } => {
+                if let Some(node) = node.clone() {
+                    let restored = graph.add_node(node);
+                    debug_assert_eq!(restored, *index);
+                }
+                for (edge_index, edge) in edges.iter().rev() {
+                    let restored = graph.add_edge(edge.source, edge.target, edge.clone());
+                    debug_assert_eq!(restored, *edge_index);
+                }
+            }
+            GraphCommand::AddEdge { index, .. } => {
+                if let Some(index) = index {
+                    graph.remove_edge(*index);
+                }
+            }
+            GraphCommand::RemoveEdge { index, edge } => {
+                if let Some(edge) = edge.clone() {
+                    let restored = graph.add_edge(edge.source, edge.target, edge);
+                    debug_assert_eq!(restored, *index);
+                }
+            }
+            GraphCommand::MoveNodes { nodes, delta } => {
+                for &node_index in nodes.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.position -= *delta;
+                    }
+                }
+            }
+            GraphCommand::SetNodePositions { positions } => {
+                for &(node_index, old_position, _) in positions.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.position = old_position;
+                    }
+                }
+            }
+            GraphCommand::SetNodeText {
+                index, old_text, ..
+            } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.text = old_text.clone();
+                }
+            }
+            GraphCommand::SetNodeNote {
+                index, old_note, ..
+            } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.note = old_note.clone();
+                }
+            }
+            GraphCommand::SetNodeCollapsed { index, collapsed } => {
+                if let Some(node) = graph.node_weight_mut(*index) {
+                    node.collapsed = !*collapsed;
+                }
+            }
+            GraphCommand::SetNodeWidths { widths } => {
+                for &(node_index, old_width, _) in widths.iter() {
+                    if let Some(node) = graph.node_weight_mut(node_index) {
+                        node.width = old_width;
+                    }
+                }
+            }
+            GraphCommand::UpdateEdge {
+                index, old_edge, ..
+            } => {
+                if let Some(edge) = graph.edge_weight_mut(*index) {
+                    *edge = old_edge.clone();
+                }
+            }
+            GraphCommand::Batch(commands) => {
+                for command in commands.iter_mut().rev() {
+                    command.revert(graph);
+                }
+            }
+        }
+    }
+
+    /// 尝试把紧随其后的同类命令合并进来，用于拖动和连续输入
+    pub fn merge(&mut self, next: &GraphCommand) -> bool {
+        match (self, next) {
+            (
+                GraphCommand::MoveNodes { nodes, delta },
+                GraphCommand::MoveNodes {
+                    nodes: next_nodes,
+                    delta: next_delta,
+                },
+            ) if nodes == next_nodes => {
+                *delta += *next_delta;
+                true
+            }
+            (
+                GraphCommand::SetNodeText {
+                    index, new_text, ..
+                },
+                GraphCommand::SetNodeText {
+                    index: next_index,
+                    new_text: next_text,
+                    ..
+                },
+            ) if index == next_index => {
+                *new_text = next_text.clone();
+                true
+            }
+            (
+                GraphCommand::SetNodeNote {
+                    index, new_note, ..
+                },
+                GraphCommand::SetNodeNote {
+                    index: next_index,
+                    new_note: next_note,
+                    ..
+                },
+            ) if index == next_index => {
+                *new_note = next_note.clone();
+                true
+            }
+            (
+                GraphCommand::UpdateEdge {
+                    index, new_edge, ..
+                },
+                GraphCommand::UpdateEdge {
+                    index: next_index,
+                    new_edge: next_edge,
+                    ..
+                },
+            ) if index == next_index => {
+                *new_edge = next_edge.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
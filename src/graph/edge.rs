@@ -3,8 +3,9 @@ use std::fmt::Display;
 use petgraph::graph::NodeIndex;
 
 use crate::{
+    geometry::polyline_midpoint,
     resource::CanvasStateResource,
-    ui::{bezier::BezierEdge, line_edge::LineEdge},
+    ui::{bezier::BezierEdge, line_edge::LineEdge, orthogonal_edge::OrthogonalEdge},
 };
 
 use super::anchor::{BezierAnchor, LineAnchor};
@@ -17,6 +18,13 @@ pub struct Edge {
     pub text: Option<String>,
     pub bezier_edge: BezierEdge,
     pub line_edge: LineEdge,
+    #[serde(default)]
+    pub orthogonal_edge: OrthogonalEdge,
+    #[serde(default)]
+    pub routing: EdgeRouting,
+    /// 边的样式，为 None 时沿用 `Graph::edge_type`（旧文件中的边没有该字段）
+    #[serde(default)]
+    pub edge_type: Option<EdgeType>,
 }
 
 impl Edge {
@@ -43,14 +51,80 @@ impl Edge {
                 LineAnchor::new(source_canvas_pos),
                 LineAnchor::new(target_canvas_pos),
             ),
+            orthogonal_edge: OrthogonalEdge::new(vec![source_canvas_pos, target_canvas_pos]),
+            routing: EdgeRouting::Automatic,
+            edge_type: None,
+        }
+    }
+
+    /// 标签位置：按弧长取边的中点，同时返回该处的切线方向
+    pub fn label_anchor(&self, edge_type: &EdgeType) -> Option<(egui::Pos2, egui::Vec2)> {
+        match edge_type {
+            EdgeType::Line => polyline_midpoint(&[
+                self.line_edge.source.canvas_pos,
+                self.line_edge.target.canvas_pos,
+            ]),
+            EdgeType::Bezier => polyline_midpoint(&self.bezier_edge.sample_points(50)),
+            EdgeType::Orthogonal => polyline_midpoint(&self.orthogonal_edge.points),
+        }
+    }
+
+    /// 边的几何形状在画布上的包围盒，贝塞尔边包含所有控制柄
+    pub fn canvas_bounds(&self, edge_type: &EdgeType) -> egui::Rect {
+        match edge_type {
+            EdgeType::Line => egui::Rect::from_two_pos(
+                self.line_edge.source.canvas_pos,
+                self.line_edge.target.canvas_pos,
+            ),
+            EdgeType::Bezier => egui::Rect::from_points(
+                &self
+                    .bezier_edge
+                    .full_anchors()
+                    .iter()
+                    .flat_map(|anchor| {
+                        [
+                            anchor.canvas_pos,
+                            anchor.handle_in_canvas_pos,
+                            anchor.handle_out_canvas_pos,
+                        ]
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            EdgeType::Orthogonal => egui::Rect::from_points(&self.orthogonal_edge.points),
+        }
+    }
+
+    /// 画布坐标下点到边的距离，按给定的边类型计算
+    pub fn distance_to(&self, edge_type: &EdgeType, canvas_pos: egui::Pos2) -> f32 {
+        match edge_type {
+            EdgeType::Line => self.line_edge.distance_to(canvas_pos),
+            EdgeType::Bezier => self.bezier_edge.distance_to(canvas_pos),
+            EdgeType::Orthogonal => self.orthogonal_edge.distance_to(canvas_pos),
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+/// 贝塞尔边首尾控制柄的路由方式
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum EdgeRouting {
+    /// 每帧根据节点位置重新生成控制柄
+    #[default]
+    Automatic,
+    /// 保留用户调整过的控制柄偏移，随节点移动
+    Pinned,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
 pub enum EdgeType {
     Line,
     Bezier,
+    /// 直角折线，绕开其他节点
+    Orthogonal,
+}
+
+impl EdgeType {
+    /// 所有可选的边样式，用于菜单和下拉框
+    pub const ALL: [EdgeType; 3] = [EdgeType::Bezier, EdgeType::Line, EdgeType::Orthogonal];
 }
 
 impl Display for EdgeType {
@@ -58,6 +132,7 @@ impl Display for EdgeType {
         let edge_type_str = match self {
             EdgeType::Line => "Line",
             EdgeType::Bezier => "Bezier",
+            EdgeType::Orthogonal => "Orthogonal",
         };
         write!(f, "{}", edge_type_str)
     }
@@ -1,18 +1,30 @@
 use std::sync::Arc;
 
+use egui::Id;
+
 use crate::graph::node::Node;
 use crate::resource::{CanvasStateResource, GraphResource};
-use crate::ui::bezier::BezierEdge;
+use crate::ui::bezier::{BezierEdge, DragType};
 use crate::ui::edge::EdgeWidget;
 use crate::ui::line_edge::LineEdge;
 use crate::ui::node_render_observer::NodeRenderObserver;
+use crate::ui::orthogonal_edge::OrthogonalEdge;
 use petgraph::graph::{EdgeIndex, NodeIndex};
 use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 
 use crate::ui::node::NodeWidget;
 
-use super::edge::{Edge, EdgeType};
+use crate::layout::align::{arrange_positions, Arrange};
+use crate::layout::LayoutAnimation;
+
+use super::anchor::BezierAnchor;
+use super::command::GraphCommand;
+use super::edge::{Edge, EdgeRouting, EdgeType};
+use super::helpers::edge_label_id;
+use super::history::CommandHistory;
+use super::render_info::{EdgeLabelRenderInfo, NodeRenderInfo};
 use super::selection::GraphSelection;
+use super::spatial::SpatialIndex;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct Graph {
@@ -22,6 +34,20 @@ pub struct Graph {
     pub selected: GraphSelection,
     #[serde(skip)]
     pub editing_node: Option<NodeIndex>,
+    #[serde(skip)]
+    pub editing_edge: Option<EdgeIndex>,
+    /// 边标签是否沿边的方向旋转
+    #[serde(default)]
+    pub rotate_edge_labels: bool,
+    #[serde(skip)]
+    pub history: CommandHistory,
+    #[serde(skip)]
+    pub layout_animation: Option<LayoutAnimation>,
+    #[serde(skip)]
+    pub spatial_index: SpatialIndex,
+    /// 不经过撤销历史的修改（默认边样式、标签旋转）的修订号
+    #[serde(skip)]
+    settings_revision: u64,
 }
 
 impl Default for Graph {
@@ -31,13 +57,40 @@ impl Default for Graph {
             graph: petgraph::stable_graph::StableGraph::new(),
             selected: GraphSelection::None,
             editing_node: None,
+            editing_edge: None,
+            rotate_edge_labels: false,
+            history: CommandHistory::default(),
+            layout_animation: None,
+            spatial_index: SpatialIndex::default(),
+            settings_revision: 0,
         }
     }
 }
 
 impl Graph {
+    /// 只复制会保存到文件中的部分，不复制撤销历史、空间索引等运行时状态
+    pub fn clone_document(&self) -> Self {
+        Self {
+            edge_type: self.edge_type,
+            graph: self.graph.clone(),
+            rotate_edge_labels: self.rotate_edge_labels,
+            ..Default::default()
+        }
+    }
+
     pub fn add_node(&mut self, node: Node) -> NodeIndex {
-        self.graph.add_node(node)
+        let mut command = GraphCommand::add_node(node);
+        command.apply(&mut self.graph);
+        let GraphCommand::AddNode {
+            index: Some(node_index),
+            ..
+        } = command
+        else {
+            unreachable!("AddNode always records its index");
+        };
+        self.spatial_index.mark_command(&command);
+        self.history.push(command);
+        node_index
     }
 
     pub fn add_node_with_edge(
@@ -46,6 +99,7 @@ impl Graph {
         src_node_index: NodeIndex,
         canvas_state_resource: CanvasStateResource,
     ) -> NodeIndex {
+        self.history.begin_batch();
         let node = node.clone();
         let dst_node_index = self.add_node(node.clone());
         let src_node = self.get_node(src_node_index).unwrap();
@@ -57,6 +111,7 @@ impl Graph {
             node.clone().position,
             canvas_state_resource,
         ));
+        self.history.end_batch();
         dst_node_index
     }
 
@@ -64,7 +119,16 @@ impl Graph {
         self.graph.node_weight(node_index)
     }
 
+    /// 按节点编号查找节点
+    pub fn find_node_by_id(&self, node_id: u64) -> Option<NodeIndex> {
+        self.graph
+            .node_indices()
+            .find(|&node_index| self.graph[node_index].id == node_id)
+    }
+
     pub fn get_node_mut(&mut self, node_index: NodeIndex) -> Option<&mut Node> {
+        // 调用方可能修改位置，下次更新空间索引时重新计算
+        self.spatial_index.mark_node(node_index);
         self.graph.node_weight_mut(node_index)
     }
 
@@ -101,27 +165,111 @@ impl Graph {
     }
 
     pub fn set_editing_node(&mut self, node_index: Option<NodeIndex>) {
+        if self.editing_node != node_index {
+            // 一次编辑过程中的输入合并为一步
+            self.history.seal();
+        }
         self.editing_node = node_index;
     }
 
+    pub fn get_editing_edge(&self) -> Option<EdgeIndex> {
+        self.editing_edge
+    }
+
+    pub fn set_editing_edge(&mut self, edge_index: Option<EdgeIndex>) {
+        if self.editing_edge != edge_index {
+            self.history.seal();
+        }
+        self.editing_edge = edge_index;
+    }
+
     pub fn remove_node(&mut self, node_index: NodeIndex) {
-        let result = self.graph.remove_node(node_index);
-        println!("result: {:?}", result);
+        if !self.graph.contains_node(node_index) {
+            return;
+        }
+        self.execute(GraphCommand::remove_node(node_index));
         // self.selected_nodes.clear();
         self.editing_node = None;
     }
+
+    /// 删除多个节点，作为一个撤销步骤
+    pub fn remove_nodes(&mut self, node_indices: &[NodeIndex]) {
+        self.history.begin_batch();
+        for &node_index in node_indices {
+            self.remove_node(node_index);
+        }
+        self.history.end_batch();
+    }
+
+    /// 平移一组节点，连续的平移会合并为一个撤销步骤，直到调用 `history.seal()`
+    pub fn move_nodes(&mut self, node_indices: &[NodeIndex], delta: egui::Vec2) {
+        self.execute(GraphCommand::MoveNodes {
+            nodes: node_indices.to_vec(),
+            delta,
+        });
+    }
+
+    pub fn set_node_text(&mut self, node_index: NodeIndex, text: String) {
+        let Some(node) = self.get_node(node_index) else {
+            return;
+        };
+        if node.text == text {
+            return;
+        }
+        let old_text = node.text.clone();
+        self.execute(GraphCommand::SetNodeText {
+            index: node_index,
+            old_text,
+            new_text: text,
+        });
+    }
+
+    pub fn set_node_note(&mut self, node_index: NodeIndex, note: String) {
+        let Some(node) = self.get_node(node_index) else {
+            return;
+        };
+        if node.note == note {
+            return;
+        }
+        let old_note = node.note.clone();
+        self.execute(GraphCommand::SetNodeNote {
+            index: node_index,
+            old_note,
+            new_note: note,
+        });
+    }
 }
 
 impl Graph {
     pub fn select_edge(&mut self, edge_index: EdgeIndex) {
         match &mut self.selected {
-            GraphSelection::Edge(edges) => edges.push(edge_index),
+            GraphSelection::Edge(edges) => {
+                if !edges.contains(&edge_index) {
+                    edges.push(edge_index);
+                }
+            }
             _ => self.selected = GraphSelection::Edge(vec![edge_index]),
         }
     }
 
-    pub fn add_edge(&mut self, edge: Edge) {
-        self.graph.add_edge(edge.source, edge.target, edge);
+    pub fn get_selected_edges(&self) -> Vec<EdgeIndex> {
+        match &self.selected {
+            GraphSelection::Edge(edges) => edges.clone(),
+            _ => vec![],
+        }
+    }
+
+    pub fn is_edge_selected(&self, edge_index: EdgeIndex) -> bool {
+        match &self.selected {
+            GraphSelection::Edge(edges) => edges.contains(&edge_index),
+            _ => false,
+        }
+    }
+
+    /// 添加边，未指定样式的边使用当前默认样式
+    pub fn add_edge(&mut self, mut edge: Edge) {
+        edge.edge_type.get_or_insert(self.edge_type);
+        self.execute(GraphCommand::add_edge(edge));
     }
 
     pub fn get_edge(&self, edge_index: EdgeIndex) -> Option<&Edge> {
@@ -129,17 +277,194 @@ impl Graph {
     }
 
     pub fn remove_edge(&mut self, edge_index: EdgeIndex) {
-        self.graph.remove_edge(edge_index);
+        if self.graph.edge_weight(edge_index).is_none() {
+            return;
+        }
+        self.execute(GraphCommand::remove_edge(edge_index));
+        if self.editing_edge == Some(edge_index) {
+            self.editing_edge = None;
+        }
+    }
+
+    /// 删除多条边，作为一个撤销步骤
+    pub fn remove_edges(&mut self, edge_indices: &[EdgeIndex]) {
+        self.history.begin_batch();
+        for &edge_index in edge_indices {
+            self.remove_edge(edge_index);
+        }
+        self.history.end_batch();
     }
 
     pub fn update_bezier_edge(&mut self, edge_index: EdgeIndex, bezier_edge: BezierEdge) {
         let edge = self.graph.edge_weight_mut(edge_index).unwrap();
         edge.bezier_edge = bezier_edge;
+        self.spatial_index.mark_edge(edge_index);
+    }
+
+    /// 拖拽贝塞尔边的锚点或控制柄，连续的拖拽会合并为一个撤销步骤
+    pub fn drag_bezier_point(
+        &mut self,
+        edge_index: EdgeIndex,
+        point_index: usize,
+        drag_type: DragType,
+        delta: egui::Vec2,
+    ) {
+        self.edit_edge(edge_index, |edge| {
+            edge.bezier_edge.drag_point(point_index, drag_type, delta);
+            // 手动调整首尾控制柄后固定路由，避免每帧被自动路由覆盖
+            let last_index = edge.bezier_edge.control_anchors.len() + 1;
+            if point_index == 0 || point_index == last_index {
+                edge.routing = EdgeRouting::Pinned;
+            }
+        });
+    }
+
+    /// 在离给定画布坐标最近的位置插入中间锚点，作为单独的撤销步骤
+    pub fn insert_bezier_anchor(&mut self, edge_index: EdgeIndex, canvas_pos: egui::Pos2) {
+        self.history.seal();
+        self.edit_edge(edge_index, |edge| {
+            let (segment, t) = edge.bezier_edge.nearest_point(canvas_pos);
+            edge.bezier_edge.insert_anchor(segment, t);
+        });
+        self.history.seal();
+    }
+
+    pub fn remove_bezier_anchor(&mut self, edge_index: EdgeIndex, point_index: usize) {
+        self.history.seal();
+        self.edit_edge(edge_index, |edge| {
+            edge.bezier_edge.remove_anchor(point_index);
+        });
+        self.history.seal();
+    }
+
+    pub fn toggle_bezier_anchor_smooth(&mut self, edge_index: EdgeIndex, point_index: usize) {
+        self.history.seal();
+        self.edit_edge(edge_index, |edge| {
+            edge.bezier_edge.toggle_anchor_smooth(point_index);
+        });
+        self.history.seal();
+    }
+
+    /// 修改多条边的路由方式，作为一个撤销步骤
+    pub fn set_edge_routing(&mut self, edge_indices: &[EdgeIndex], routing: EdgeRouting) {
+        self.history.begin_batch();
+        for &edge_index in edge_indices {
+            self.edit_edge(edge_index, |edge| edge.routing = routing);
+        }
+        self.history.end_batch();
+    }
+
+    /// 把贝塞尔边的控制柄固定为沿 `direction` 方向，作为一个撤销步骤
+    ///
+    /// `direction` 是起点控制柄的方向，终点控制柄取相反方向，中间的控制锚点被清除。
+    pub fn orient_bezier_handles(&mut self, edges: &[(EdgeIndex, egui::Vec2)]) {
+        const HANDLE_LENGTH: f32 = 50.0;
+        self.history.begin_batch();
+        for &(edge_index, direction) in edges {
+            if self.edge_type_of(edge_index) != Some(EdgeType::Bezier) {
+                continue;
+            }
+            let offset = direction.normalized() * HANDLE_LENGTH;
+            self.edit_edge(edge_index, |edge| {
+                let bezier_edge = &mut edge.bezier_edge;
+                let source = bezier_edge.source_anchor.canvas_pos;
+                let target = bezier_edge.target_anchor.canvas_pos;
+                bezier_edge.source_anchor =
+                    BezierAnchor::new_smooth(source).with_handles(source + offset, source + offset);
+                bezier_edge.target_anchor =
+                    BezierAnchor::new_smooth(target).with_handles(target - offset, target - offset);
+                bezier_edge.control_anchors.clear();
+                edge.routing = EdgeRouting::Pinned;
+            });
+        }
+        self.history.end_batch();
+    }
+
+    /// 修改边的标签，空字符串表示没有标签
+    pub fn set_edge_text(&mut self, edge_index: EdgeIndex, text: String) {
+        let text = (!text.is_empty()).then_some(text);
+        if self
+            .get_edge(edge_index)
+            .is_none_or(|edge| edge.text == text)
+        {
+            return;
+        }
+        self.edit_edge(edge_index, |edge| edge.text = text);
+    }
+
+    /// 边实际使用的样式
+    pub fn edge_type_of(&self, edge_index: EdgeIndex) -> Option<EdgeType> {
+        self.get_edge(edge_index)
+            .map(|edge| edge.edge_type.unwrap_or(self.edge_type))
+    }
+
+    /// 修改多条边的样式，作为一个撤销步骤
+    pub fn set_edge_type(&mut self, edge_indices: &[EdgeIndex], edge_type: EdgeType) {
+        self.history.begin_batch();
+        for &edge_index in edge_indices {
+            self.edit_edge(edge_index, |edge| edge.edge_type = Some(edge_type));
+        }
+        self.history.end_batch();
+    }
+
+    /// 修改新建边的默认样式
+    pub fn set_default_edge_type(&mut self, edge_type: EdgeType) {
+        // 旧文件中没有样式的边先固定为原来的默认值，不随默认值变化
+        let default_edge_type = self.edge_type;
+        for edge in self.graph.edge_weights_mut() {
+            edge.edge_type.get_or_insert(default_edge_type);
+        }
+        if self.edge_type != edge_type {
+            self.edge_type = edge_type;
+            self.settings_revision += 1;
+            // 使用默认样式的边都换了形状
+            self.spatial_index.mark_all();
+        }
+    }
+
+    /// 设置边标签是否沿边的方向旋转
+    pub fn set_rotate_edge_labels(&mut self, rotate: bool) {
+        if self.rotate_edge_labels != rotate {
+            self.rotate_edge_labels = rotate;
+            self.settings_revision += 1;
+        }
+    }
+
+    /// 图的修订号：撤销历史中的当前状态和图设置的修订号，用来判断文档有没有未保存的修改
+    ///
+    /// 撤销回保存时的状态后修订号与保存时相同。
+    pub fn revision(&self) -> (u64, u64) {
+        (self.history.revision(), self.settings_revision)
+    }
+
+    /// 以可撤销的方式修改边，连续修改同一条边会合并为一个撤销步骤
+    fn edit_edge(&mut self, edge_index: EdgeIndex, f: impl FnOnce(&mut Edge)) {
+        let Some(old_edge) = self.get_edge(edge_index).cloned() else {
+            return;
+        };
+        let mut new_edge = old_edge.clone();
+        f(&mut new_edge);
+        self.execute(GraphCommand::UpdateEdge {
+            index: edge_index,
+            old_edge,
+            new_edge,
+        });
     }
 
     pub fn update_line_edge(&mut self, edge_index: EdgeIndex, line_edge: LineEdge) {
         let edge = self.graph.edge_weight_mut(edge_index).unwrap();
         edge.line_edge = line_edge;
+        self.spatial_index.mark_edge(edge_index);
+    }
+
+    pub fn update_orthogonal_edge(
+        &mut self,
+        edge_index: EdgeIndex,
+        orthogonal_edge: OrthogonalEdge,
+    ) {
+        let edge = self.graph.edge_weight_mut(edge_index).unwrap();
+        edge.orthogonal_edge = orthogonal_edge;
+        self.spatial_index.mark_edge(edge_index);
     }
 
     pub fn edge_exists(&self, src_node_index: NodeIndex, dst_node_index: NodeIndex) -> bool {
@@ -162,6 +487,142 @@ impl Graph {
         self.graph = petgraph::stable_graph::StableGraph::new();
         self.selected = GraphSelection::None;
         self.editing_node = None;
+        self.editing_edge = None;
+        self.history.clear();
+        self.layout_animation = None;
+        self.spatial_index = SpatialIndex::default();
+    }
+}
+
+impl Graph {
+    /// 执行命令并记录到撤销历史
+    pub fn execute(&mut self, mut command: GraphCommand) {
+        // 新的编辑开始前让布局动画直接到达终点，和撤销历史保持一致
+        self.finish_layout_animation();
+        command.apply(&mut self.graph);
+        self.spatial_index.mark_command(&command);
+        self.history.push(command);
+    }
+
+    /// 应用布局结果，作为一个撤销步骤；`animate` 为 true 时节点在之后几帧内移动过去
+    pub fn apply_layout(&mut self, positions: Vec<(NodeIndex, egui::Pos2)>, animate: bool) {
+        let positions = positions
+            .into_iter()
+            .filter_map(|(node_index, new_position)| {
+                let old_position = self.get_node(node_index)?.position;
+                Some((node_index, old_position, new_position))
+            })
+            .collect::<Vec<_>>();
+        if positions.is_empty() {
+            return;
+        }
+
+        self.history.seal();
+        self.execute(GraphCommand::SetNodePositions {
+            positions: positions.clone(),
+        });
+        self.history.seal();
+
+        if animate {
+            // 历史中已经是最终位置，先把节点放回原处再逐帧移动
+            let (from, to): (Vec<_>, Vec<_>) = positions
+                .into_iter()
+                .map(|(node_index, old, new)| ((node_index, old), (node_index, new)))
+                .unzip();
+            self.set_positions_raw(&from);
+            self.layout_animation = Some(LayoutAnimation::new(from, to));
+        }
+    }
+
+    /// 对齐、分布或统一宽度，作为一个撤销步骤
+    pub fn arrange_nodes(&mut self, rects: &[(NodeIndex, egui::Rect)], arrange: Arrange) {
+        if arrange != Arrange::MatchWidths {
+            self.apply_layout(arrange_positions(rects, arrange), false);
+            return;
+        }
+        if rects.len() < arrange.min_nodes() {
+            return;
+        }
+        // 统一为最宽节点的宽度
+        let width = rects
+            .iter()
+            .map(|(_, rect)| rect.width())
+            .fold(0.0, f32::max);
+        let widths = rects
+            .iter()
+            .filter_map(|&(node_index, _)| {
+                let old_width = self.get_node(node_index)?.width;
+                Some((node_index, old_width, Some(width)))
+            })
+            .collect::<Vec<_>>();
+        self.history.seal();
+        self.execute(GraphCommand::SetNodeWidths { widths });
+        self.history.seal();
+    }
+
+    /// 推进布局动画，返回动画是否仍在进行
+    pub fn step_layout_animation(&mut self, dt: f32) -> bool {
+        let Some(animation) = self.layout_animation.as_mut() else {
+            return false;
+        };
+        let positions = animation.step(dt);
+        let finished = animation.is_finished();
+        self.set_positions_raw(&positions);
+        if finished {
+            self.layout_animation = None;
+        }
+        !finished
+    }
+
+    fn finish_layout_animation(&mut self) {
+        if let Some(animation) = self.layout_animation.take() {
+            self.set_positions_raw(&animation.to);
+        }
+    }
+
+    // 直接修改节点位置，不记录历史
+    fn set_positions_raw(&mut self, positions: &[(NodeIndex, egui::Pos2)]) {
+        for &(node_index, position) in positions {
+            if let Some(node) = self.graph.node_weight_mut(node_index) {
+                node.position = position;
+                self.spatial_index.mark_node(node_index);
+            }
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        // 撤销时放弃动画，位置由命令直接还原
+        self.layout_animation = None;
+        let undone = match self.history.undo(&mut self.graph) {
+            Some(command) => {
+                self.spatial_index.mark_command(command);
+                true
+            }
+            None => false,
+        };
+        if undone {
+            self.selected.clear();
+            self.editing_node = None;
+            self.editing_edge = None;
+        }
+        undone
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.layout_animation = None;
+        let redone = match self.history.redo(&mut self.graph) {
+            Some(command) => {
+                self.spatial_index.mark_command(command);
+                true
+            }
+            None => false,
+        };
+        if redone {
+            self.selected.clear();
+            self.editing_node = None;
+            self.editing_edge = None;
+        }
+        redone
     }
 }
 
@@ -170,13 +631,34 @@ pub fn render_graph(
     graph_resource: GraphResource,
     canvas_state_resource: CanvasStateResource,
 ) {
-    let node_indices = graph_resource
-        .read_resource(|graph| graph.graph.node_indices().collect::<Vec<NodeIndex>>());
-
-    // println!("node_indices: {:?}", node_indices.len());
-
-    let edge_indices = graph_resource
-        .read_resource(|graph| graph.graph.edge_indices().collect::<Vec<EdgeIndex>>());
+    // 只渲染与视口相交的节点和边；可见边的两端节点也要渲染，保证边使用最新的节点位置
+    let viewport = canvas_state_resource
+        .read_resource(|canvas_state| canvas_state.to_canvas_rect(ui.clip_rect()));
+    // 折叠的子树不在索引中，不会渲染；新隐藏的节点和边清除渲染信息，避免被命中测试选中
+    let (node_indices, edge_indices, (hidden, hidden_edges)) =
+        graph_resource.with_resource(|graph| {
+            // 输入处理中移动的节点和边在渲染前更新到索引中
+            graph.update_spatial_index();
+            let edge_indices = graph.edges_in_rect(viewport);
+            let mut node_indices = graph.nodes_in_rect(viewport);
+            node_indices.extend(
+                edge_indices
+                    .iter()
+                    .filter_map(|edge_index| graph.graph.edge_endpoints(*edge_index))
+                    .flat_map(|(source, target)| [source, target]),
+            );
+            node_indices.sort();
+            node_indices.dedup();
+            (node_indices, edge_indices, graph.take_newly_hidden())
+        });
+    ui.ctx().data_mut(|d| {
+        for node_index in &hidden {
+            d.remove::<NodeRenderInfo>(Id::new(node_index.index().to_string()));
+        }
+        for edge_index in &hidden_edges {
+            d.remove::<EdgeLabelRenderInfo>(edge_label_id(*edge_index));
+        }
+    });
 
     for edge_index in edge_indices {
         ui.add(EdgeWidget {
@@ -1,7 +1,7 @@
 use egui::{Id, Pos2};
-use petgraph::stable_graph::NodeIndex;
+use petgraph::stable_graph::{EdgeIndex, NodeIndex};
 
-use super::render_info::NodeRenderInfo;
+use super::render_info::{EdgeLabelRenderInfo, NodeRenderInfo};
 
 pub fn get_node_render_info(node_index: NodeIndex, ui: &egui::Ui) -> Option<NodeRenderInfo> {
     let node_render_info: Option<NodeRenderInfo> = ui
@@ -18,3 +18,22 @@ pub fn node_rect_center(node_index: NodeIndex, ui: &egui::Ui) -> Pos2 {
 
     node_render_info.canvas_center()
 }
+
+pub fn edge_label_id(edge_index: EdgeIndex) -> Id {
+    Id::new(("edge_label", edge_index.index()))
+}
+
+pub fn get_edge_label_render_info(
+    edge_index: EdgeIndex,
+    ctx: &egui::Context,
+) -> Option<EdgeLabelRenderInfo> {
+    ctx.data(|reader| reader.get_temp(edge_label_id(edge_index)))
+}
+
+/// 折叠按钮的半径（画布坐标）
+pub const COLLAPSE_TOGGLE_RADIUS: f32 = 7.0;
+
+/// 节点右侧折叠按钮的圆心（画布坐标）
+pub fn collapse_toggle_center(node_canvas_rect: egui::Rect) -> Pos2 {
+    node_canvas_rect.right_center() + egui::vec2(COLLAPSE_TOGGLE_RADIUS + 4.0, 0.0)
+}
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use petgraph::stable_graph::StableGraph;
+
+use super::command::GraphCommand;
+use super::edge::Edge;
+use super::node::Node;
+
+/// 默认最多保留的撤销步数
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// 撤销/重做历史
+#[derive(Clone, Debug)]
+pub struct CommandHistory {
+    /// 每条命令带一个编号，命令合并后换新编号
+    undo_stack: VecDeque<(u64, GraphCommand)>,
+    redo_stack: Vec<(u64, GraphCommand)>,
+    limit: usize,
+    /// 为 true 时下一条命令不会与栈顶合并
+    sealed: bool,
+    /// 正在收集的一组命令
+    batch: Option<Vec<GraphCommand>>,
+    /// `begin_batch` 的嵌套层数，最外层结束时才合并
+    batch_depth: usize,
+    /// 下一条命令的编号，从 1 开始，0 表示没有任何命令的初始状态
+    next_id: u64,
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl CommandHistory {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            limit: limit.max(1),
+            sealed: true,
+            batch: None,
+            batch_depth: 0,
+            next_id: 1,
+        }
+    }
+
+    /// 记录一条已经执行过的命令
+    pub fn push(&mut self, command: GraphCommand) {
+        self.redo_stack.clear();
+
+        if let Some(batch) = self.batch.as_mut() {
+            batch.push(command);
+            return;
+        }
+
+        if !self.sealed {
+            if let Some((id, last)) = self.undo_stack.back_mut() {
+                if last.merge(&command) {
+                    // 合并后是新的状态，不能与合并前的编号相同
+                    *id = self.next_id;
+                    self.next_id += 1;
+                    return;
+                }
+            }
+        }
+
+        self.undo_stack.push_back((self.next_id, command));
+        self.next_id += 1;
+        while self.undo_stack.len() > self.limit {
+            self.undo_stack.pop_front();
+        }
+        self.sealed = false;
+    }
+
+    /// 开始一组命令，直到 `end_batch` 前记录的命令会合并为一个撤销步骤，可以嵌套
+    pub fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(Vec::new());
+        }
+        self.batch_depth += 1;
+    }
+
+    pub fn end_batch(&mut self) {
+        self.batch_depth = self.batch_depth.saturating_sub(1);
+        if self.batch_depth == 0 {
+            self.flush_batch();
+        }
+    }
+
+    /// 立即结束所有未完成的组
+    fn flush_batch(&mut self) {
+        self.batch_depth = 0;
+        let Some(mut commands) = self.batch.take() else {
+            return;
+        };
+        self.sealed = true;
+        match commands.len() {
+            0 => {}
+            1 => self.push(commands.remove(0)),
+            _ => self.push(GraphCommand::Batch(commands)),
+        }
+        self.sealed = true;
+    }
+
+    /// 结束当前手势，之后的命令单独成为一步
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// 撤销最近的命令，返回被撤销的命令
+    pub fn undo(&mut self, graph: &mut StableGraph<Node, Edge>) -> Option<&GraphCommand> {
+        self.flush_batch();
+        self.sealed = true;
+        let (id, mut command) = self.undo_stack.pop_back()?;
+        command.revert(graph);
+        self.redo_stack.push((id, command));
+        self.redo_stack.last().map(|(_, command)| command)
+    }
+
+    /// 重做最近撤销的命令，返回被重做的命令
+    pub fn redo(&mut self, graph: &mut StableGraph<Node, Edge>) -> Option<&GraphCommand> {
+        self.flush_batch();
+        self.sealed = true;
+        let (id, mut command) = self.redo_stack.pop()?;
+        command.apply(graph);
+        self.undo_stack.push_back((id, command));
+        self.undo_stack.back().map(|(_, command)| command)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// 当前状态的编号：撤销栈顶命令的编号
+    ///
+    /// 撤销或重做回到某个状态时编号与当时相同，用来判断文档是否回到了保存时的状态。
+    pub fn revision(&self) -> u64 {
+        self.undo_stack.back().map_or(0, |(id, _)| *id)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.sealed = true;
+        self.batch = None;
+        self.batch_depth = 0;
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Pos2, Vec2};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::layout::mind_map::{mind_map_layout, MIND_MAP_LEVEL_GAP};
+use crate::layout::DEFAULT_NODE_SIZE;
+use crate::resource::CanvasStateResource;
+
+use super::command::GraphCommand;
+use super::graph_impl::Graph;
+use super::node::Node;
+
+// 思维导图把出边看作父节点指向子节点
+impl Graph {
+    /// 父节点：第一条入边的起点
+    pub fn tree_parent(&self, node_index: NodeIndex) -> Option<NodeIndex> {
+        self.graph
+            .edges_directed(node_index, Direction::Incoming)
+            .filter(|edge| edge.source() != node_index)
+            .min_by_key(|edge| edge.id())
+            .map(|edge| edge.source())
+    }
+
+    /// 沿父节点向上找到的根节点，成环时停在环上最先重复的节点
+    pub fn tree_root(&self, node_index: NodeIndex) -> NodeIndex {
+        let mut visited = HashSet::from([node_index]);
+        let mut root = node_index;
+        while let Some(parent) = self.tree_parent(root) {
+            if !visited.insert(parent) {
+                break;
+            }
+            root = parent;
+        }
+        root
+    }
+
+    /// 子节点，按当前的纵向位置从上到下排序
+    pub fn tree_children(&self, node_index: NodeIndex) -> Vec<NodeIndex> {
+        let mut children: Vec<NodeIndex> = self
+            .graph
+            .edges_directed(node_index, Direction::Outgoing)
+            .map(|edge| edge.target())
+            .filter(|target| *target != node_index)
+            .collect();
+        children.sort_by(|a, b| {
+            let y = |n: &NodeIndex| self.get_node(*n).map_or(0.0, |node| node.position.y);
+            y(a).total_cmp(&y(b)).then(a.cmp(b))
+        });
+        children.dedup();
+        children
+    }
+
+    pub fn has_tree_children(&self, node_index: NodeIndex) -> bool {
+        self.graph
+            .edges_directed(node_index, Direction::Outgoing)
+            .any(|edge| edge.target() != node_index)
+    }
+
+    /// 是否显示折叠按钮：折叠的节点总是显示，有子节点的节点只在选中时显示，
+    /// 避免普通图中每个有出边的节点都带着按钮
+    pub fn shows_collapse_toggle(&self, node_index: NodeIndex) -> bool {
+        let Some(node) = self.get_node(node_index) else {
+            return false;
+        };
+        node.collapsed || (self.is_node_selected(node_index) && self.has_tree_children(node_index))
+    }
+
+    /// 被折叠的祖先隐藏的节点
+    pub fn hidden_nodes(&self) -> HashSet<NodeIndex> {
+        let mut hidden = HashSet::new();
+        for node_index in self.graph.node_indices() {
+            if !self.graph[node_index].collapsed {
+                continue;
+            }
+            let mut stack = self.tree_children(node_index);
+            while let Some(child) = stack.pop() {
+                // 回到折叠节点自身的环不隐藏它
+                if child != node_index && hidden.insert(child) {
+                    stack.extend(self.tree_children(child));
+                }
+            }
+        }
+        hidden
+    }
+
+    /// 任一端点被隐藏的边也隐藏
+    pub fn is_edge_hidden(&self, edge_index: EdgeIndex, hidden: &HashSet<NodeIndex>) -> bool {
+        self.graph
+            .edge_endpoints(edge_index)
+            .is_none_or(|(source, target)| hidden.contains(&source) || hidden.contains(&target))
+    }
+
+    /// 添加子节点并重新整理所在的树，作为一个撤销步骤
+    pub fn add_child_node(
+        &mut self,
+        parent: NodeIndex,
+        node_id: u64,
+        canvas_state_resource: CanvasStateResource,
+        sizes: &HashMap<NodeIndex, Vec2>,
+    ) -> Option<NodeIndex> {
+        let parent_node = self.get_node(parent)?;
+        let (parent_position, parent_collapsed) = (parent_node.position, parent_node.collapsed);
+        let parent_size = sizes.get(&parent).copied().unwrap_or(DEFAULT_NODE_SIZE);
+        // 新节点排在最后一个子节点之后
+        let y = self
+            .tree_children(parent)
+            .last()
+            .and_then(|last| self.get_node(*last))
+            .map_or(parent_position.y, |last| last.position.y + 1.0);
+        let position = Pos2::new(parent_position.x + parent_size.x + MIND_MAP_LEVEL_GAP, y);
+
+        self.history.begin_batch();
+        if parent_collapsed {
+            self.execute(GraphCommand::SetNodeCollapsed {
+                index: parent,
+                collapsed: false,
+            });
+        }
+        let node_index = self.add_node_with_edge(
+            Node {
+                id: node_id,
+                position,
+                text: String::new(),
+                note: String::new(),
+                collapsed: false,
+                width: None,
+            },
+            parent,
+            canvas_state_resource,
+        );
+        self.arrange_mind_map(node_index, sizes);
+        self.history.end_batch();
+        Some(node_index)
+    }
+
+    /// 在节点之后添加兄弟节点；根节点没有父节点，改为添加子节点
+    pub fn add_sibling_node(
+        &mut self,
+        node_index: NodeIndex,
+        node_id: u64,
+        canvas_state_resource: CanvasStateResource,
+        sizes: &HashMap<NodeIndex, Vec2>,
+    ) -> Option<NodeIndex> {
+        let Some(parent) = self.tree_parent(node_index) else {
+            return self.add_child_node(node_index, node_id, canvas_state_resource, sizes);
+        };
+        let sibling_position = self.get_node(node_index)?.position;
+
+        self.history.begin_batch();
+        let new_index = self.add_node_with_edge(
+            Node {
+                id: node_id,
+                // 稍微靠下，排序时紧跟在当前节点之后
+                position: sibling_position + Vec2::new(0.0, 0.5),
+                text: String::new(),
+                note: String::new(),
+                collapsed: false,
+                width: None,
+            },
+            parent,
+            canvas_state_resource,
+        );
+        self.arrange_mind_map(new_index, sizes);
+        self.history.end_batch();
+        Some(new_index)
+    }
+
+    /// 折叠或展开节点的子树，并重新整理所在的树
+    pub fn toggle_collapsed(&mut self, node_index: NodeIndex, sizes: &HashMap<NodeIndex, Vec2>) {
+        let Some(node) = self.get_node(node_index) else {
+            return;
+        };
+        let collapsed = !node.collapsed;
+
+        self.history.seal();
+        self.history.begin_batch();
+        self.execute(GraphCommand::SetNodeCollapsed {
+            index: node_index,
+            collapsed,
+        });
+        self.arrange_mind_map(node_index, sizes);
+        self.history.end_batch();
+
+        // 隐藏的节点不能保持选中
+        if collapsed {
+            let hidden = self.hidden_nodes();
+            let selected = self.get_selected_nodes();
+            if selected.iter().any(|n| hidden.contains(n)) {
+                let visible: Vec<NodeIndex> = selected
+                    .into_iter()
+                    .filter(|n| !hidden.contains(n))
+                    .collect();
+                self.selected.clear();
+                if !visible.is_empty() {
+                    self.select_nodes(visible);
+                }
+            }
+        }
+    }
+
+    /// 整理节点所在的思维导图，根节点保持不动
+    pub fn arrange_mind_map(&mut self, node_index: NodeIndex, sizes: &HashMap<NodeIndex, Vec2>) {
+        let root = self.tree_root(node_index);
+        let positions = mind_map_layout(self, sizes, root);
+        self.apply_layout(positions, true);
+    }
+}
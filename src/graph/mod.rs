@@ -1,8 +1,12 @@
 pub mod anchor;
+pub mod command;
 pub mod edge;
 pub mod graph_impl;
 pub mod helpers;
+pub mod history;
+pub mod mind_map;
 pub mod node;
 pub mod node_observer;
 pub mod render_info;
 pub mod selection;
+pub mod spatial;
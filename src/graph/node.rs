@@ -4,5 +4,11 @@ pub struct Node {
     pub position: egui::Pos2,
     pub text: String,
     pub note: String,
+    /// 是否折叠了子树（思维导图中沿出边的后代节点）
+    #[serde(default)]
+    pub collapsed: bool,
+    /// 固定的最小宽度（画布坐标），为空时按文本自动计算
+    #[serde(default)]
+    pub width: Option<f32>,
     // pub render_info: Option<NodeRenderInfo>,
 }
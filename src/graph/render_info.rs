@@ -18,3 +18,9 @@ impl NodeRenderInfo {
 pub struct EdgeRenderInfo {
     pub canvas_rect: egui::Rect,
 }
+
+/// 边标签的绘制信息，供命中测试使用
+#[derive(Copy, Clone, Debug)]
+pub struct EdgeLabelRenderInfo {
+    pub canvas_rect: egui::Rect,
+}
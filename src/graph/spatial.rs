@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Pos2, Rect, Vec2};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use petgraph::Direction;
+
+use crate::geometry::quadtree::QuadTree;
+use crate::layout::DEFAULT_NODE_SIZE;
+
+use super::command::GraphCommand;
+use super::graph_impl::Graph;
+
+/// 节点和边在画布上的空间索引，用于命中测试、框选和视口裁剪
+///
+/// 修改节点位置、尺寸、边或折叠状态时只记录受影响的元素，每帧开始时更新这些元素，
+/// 不再遍历整个图。折叠隐藏的节点和边不在索引中。
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    nodes: QuadTree<NodeIndex>,
+    edges: QuadTree<EdgeIndex>,
+    /// 节点上一次渲染时的画布尺寸
+    node_sizes: HashMap<NodeIndex, Vec2>,
+    /// 节点和边在索引中的矩形，更新时用来找到旧的条目
+    node_rects: HashMap<NodeIndex, Rect>,
+    edge_rects: HashMap<EdgeIndex, Rect>,
+    /// 被折叠的祖先隐藏的节点
+    hidden_nodes: HashSet<NodeIndex>,
+    /// 上次更新后新隐藏的节点和边，渲染时清除它们的渲染信息
+    newly_hidden: (Vec<NodeIndex>, Vec<EdgeIndex>),
+    dirty_nodes: HashSet<NodeIndex>,
+    dirty_edges: HashSet<EdgeIndex>,
+    /// 图的结构或折叠状态变了，需要重新计算隐藏的节点
+    hidden_dirty: bool,
+    /// 需要按整个图重新构建，例如刚打开文档时
+    needs_rebuild: bool,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            nodes: QuadTree::default(),
+            edges: QuadTree::default(),
+            node_sizes: HashMap::new(),
+            node_rects: HashMap::new(),
+            edge_rects: HashMap::new(),
+            hidden_nodes: HashSet::new(),
+            newly_hidden: (Vec::new(), Vec::new()),
+            dirty_nodes: HashSet::new(),
+            dirty_edges: HashSet::new(),
+            hidden_dirty: true,
+            needs_rebuild: true,
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// 节点的位置或尺寸变了，连带更新相连的边
+    pub fn mark_node(&mut self, node_index: NodeIndex) {
+        self.dirty_nodes.insert(node_index);
+    }
+
+    /// 边的几何形状变了
+    pub fn mark_edge(&mut self, edge_index: EdgeIndex) {
+        self.dirty_edges.insert(edge_index);
+    }
+
+    /// 下次更新时重新构建整个索引
+    pub fn mark_all(&mut self) {
+        self.needs_rebuild = true;
+        self.hidden_dirty = true;
+    }
+
+    /// 记录命令执行或撤销时影响的节点和边
+    pub fn mark_command(&mut self, command: &GraphCommand) {
+        match command {
+            GraphCommand::AddNode { index, .. } => {
+                if let Some(index) = index {
+                    self.mark_node(*index);
+                }
+                self.hidden_dirty = true;
+            }
+            GraphCommand::RemoveNode { index, edges, .. } => {
+                self.mark_node(*index);
+                for (edge_index, _) in edges {
+                    self.mark_edge(*edge_index);
+                }
+                self.hidden_dirty = true;
+            }
+            GraphCommand::AddEdge { index, .. } => {
+                if let Some(index) = index {
+                    self.mark_edge(*index);
+                }
+                self.hidden_dirty = true;
+            }
+            GraphCommand::RemoveEdge { index, .. } => {
+                self.mark_edge(*index);
+                self.hidden_dirty = true;
+            }
+            GraphCommand::MoveNodes { nodes, .. } => {
+                for node_index in nodes {
+                    self.mark_node(*node_index);
+                }
+            }
+            GraphCommand::SetNodePositions { positions } => {
+                for (node_index, _, _) in positions {
+                    self.mark_node(*node_index);
+                }
+            }
+            GraphCommand::SetNodeWidths { widths } => {
+                for (node_index, _, _) in widths {
+                    self.mark_node(*node_index);
+                }
+            }
+            GraphCommand::SetNodeCollapsed { .. } => self.hidden_dirty = true,
+            // 文本改变后的尺寸在节点重新渲染时通过 `set_node_size` 更新
+            GraphCommand::SetNodeText { .. } | GraphCommand::SetNodeNote { .. } => {}
+            GraphCommand::UpdateEdge { index, .. } => self.mark_edge(*index),
+            GraphCommand::Batch(commands) => {
+                for command in commands {
+                    self.mark_command(command);
+                }
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// 记录节点渲染出的画布尺寸，尺寸变化时更新索引
+    pub fn set_node_size(&mut self, node_index: NodeIndex, size: Vec2) {
+        if self.spatial_index.node_sizes.insert(node_index, size) != Some(size) {
+            self.spatial_index.mark_node(node_index);
+        }
+    }
+
+    /// 节点最近一次渲染的画布尺寸，还没有渲染过时为空
+    pub fn node_size(&self, node_index: NodeIndex) -> Option<Vec2> {
+        self.spatial_index.node_sizes.get(&node_index).copied()
+    }
+
+    /// 节点在画布上的矩形：当前位置加上最近一次渲染的尺寸
+    pub fn node_rect(&self, node_index: NodeIndex) -> Option<Rect> {
+        let position = self.get_node(node_index)?.position;
+        let size = self.node_size(node_index).unwrap_or(DEFAULT_NODE_SIZE);
+        Some(Rect::from_min_size(position, size))
+    }
+
+    /// 节点是否被折叠的祖先隐藏，以上次更新索引时为准
+    pub fn is_node_hidden(&self, node_index: NodeIndex) -> bool {
+        self.spatial_index.hidden_nodes.contains(&node_index)
+    }
+
+    /// 取出上次更新索引后新隐藏的节点和边
+    pub fn take_newly_hidden(&mut self) -> (Vec<NodeIndex>, Vec<EdgeIndex>) {
+        std::mem::take(&mut self.spatial_index.newly_hidden)
+    }
+
+    /// 更新修改过的节点和边在索引中的位置
+    pub fn update_spatial_index(&mut self) {
+        if self.spatial_index.hidden_dirty {
+            self.update_hidden_nodes();
+        }
+        if self.spatial_index.needs_rebuild {
+            self.rebuild_spatial_index();
+            return;
+        }
+
+        let dirty_nodes = std::mem::take(&mut self.spatial_index.dirty_nodes);
+        let mut dirty_edges = std::mem::take(&mut self.spatial_index.dirty_edges);
+        for &node_index in &dirty_nodes {
+            dirty_edges.extend(
+                self.graph
+                    .edges_directed(node_index, Direction::Outgoing)
+                    .chain(self.graph.edges_directed(node_index, Direction::Incoming))
+                    .map(|edge| edge.id()),
+            );
+        }
+        // 先更新节点，边的范围依赖两端节点的新矩形
+        for node_index in dirty_nodes {
+            self.update_node_entry(node_index);
+        }
+        for edge_index in dirty_edges {
+            self.update_edge_entry(edge_index);
+        }
+
+        // 移出构建时范围的元素太多时重新构建
+        if self.spatial_index.nodes.needs_rebuild() || self.spatial_index.edges.needs_rebuild() {
+            self.rebuild_spatial_index();
+        }
+    }
+
+    /// 重新计算隐藏的节点，显示状态变化的节点需要更新索引
+    fn update_hidden_nodes(&mut self) {
+        let hidden = self.hidden_nodes();
+        let old = std::mem::replace(&mut self.spatial_index.hidden_nodes, hidden);
+        let index = &mut self.spatial_index;
+        for &node_index in old.symmetric_difference(&index.hidden_nodes) {
+            index.dirty_nodes.insert(node_index);
+        }
+        for &node_index in index.hidden_nodes.difference(&old) {
+            index.newly_hidden.0.push(node_index);
+            index.newly_hidden.1.extend(
+                self.graph
+                    .edges_directed(node_index, Direction::Outgoing)
+                    .chain(self.graph.edges_directed(node_index, Direction::Incoming))
+                    .map(|edge| edge.id()),
+            );
+        }
+        index.hidden_dirty = false;
+    }
+
+    /// 节点在索引中的矩形，隐藏或已删除的节点为空
+    fn indexed_node_rect(&self, node_index: NodeIndex) -> Option<Rect> {
+        if self.is_node_hidden(node_index) {
+            return None;
+        }
+        self.node_rect(node_index)
+    }
+
+    /// 边在索引中的范围，任一端点不在索引中时为空
+    fn indexed_edge_rect(&self, edge_index: EdgeIndex) -> Option<Rect> {
+        let (source, target) = self.graph.edge_endpoints(edge_index)?;
+        let source = self.spatial_index.node_rects.get(&source)?;
+        let target = self.spatial_index.node_rects.get(&target)?;
+        // 离屏的边不会更新几何形状，再并上两端节点保证包含当前的端点
+        let mut bounds = source.union(*target);
+        if let Some(edge_type) = self.edge_type_of(edge_index) {
+            bounds = bounds.union(self.graph[edge_index].canvas_bounds(&edge_type));
+        }
+        Some(bounds)
+    }
+
+    fn update_node_entry(&mut self, node_index: NodeIndex) {
+        if !self.graph.contains_node(node_index) {
+            self.spatial_index.node_sizes.remove(&node_index);
+        }
+        let rect = self.indexed_node_rect(node_index);
+        let index = &mut self.spatial_index;
+        let old = index.node_rects.get(&node_index).copied();
+        if old == rect {
+            return;
+        }
+        if let Some(old) = old {
+            index.nodes.remove(old, node_index);
+        }
+        match rect {
+            Some(rect) => {
+                index.nodes.insert(rect, node_index);
+                index.node_rects.insert(node_index, rect);
+            }
+            None => {
+                index.node_rects.remove(&node_index);
+            }
+        }
+    }
+
+    fn update_edge_entry(&mut self, edge_index: EdgeIndex) {
+        let rect = self.indexed_edge_rect(edge_index);
+        let index = &mut self.spatial_index;
+        let old = index.edge_rects.get(&edge_index).copied();
+        if old == rect {
+            return;
+        }
+        if let Some(old) = old {
+            index.edges.remove(old, edge_index);
+        }
+        match rect {
+            Some(rect) => {
+                index.edges.insert(rect, edge_index);
+                index.edge_rects.insert(edge_index, rect);
+            }
+            None => {
+                index.edge_rects.remove(&edge_index);
+            }
+        }
+    }
+
+    /// 按节点和边的当前几何形状重新构建整个索引
+    fn rebuild_spatial_index(&mut self) {
+        self.spatial_index
+            .node_sizes
+            .retain(|node_index, _| self.graph.contains_node(*node_index));
+
+        self.spatial_index.node_rects = self
+            .graph
+            .node_indices()
+            .filter_map(|node_index| Some((node_index, self.indexed_node_rect(node_index)?)))
+            .collect();
+        self.spatial_index.edge_rects = self
+            .graph
+            .edge_references()
+            .filter_map(|edge| Some((edge.id(), self.indexed_edge_rect(edge.id())?)))
+            .collect();
+
+        let index = &mut self.spatial_index;
+        index.nodes = QuadTree::build(
+            index
+                .node_rects
+                .iter()
+                .map(|(node_index, rect)| (*rect, *node_index))
+                .collect(),
+        );
+        index.edges = QuadTree::build(
+            index
+                .edge_rects
+                .iter()
+                .map(|(edge_index, rect)| (*rect, *edge_index))
+                .collect(),
+        );
+        index.dirty_nodes.clear();
+        index.dirty_edges.clear();
+        index.needs_rebuild = false;
+    }
+
+    /// 与 `rect` 相交的可见节点，按索引排序
+    pub fn nodes_in_rect(&self, rect: Rect) -> Vec<NodeIndex> {
+        let mut nodes: Vec<NodeIndex> = self
+            .spatial_index
+            .nodes
+            .query_rect(rect)
+            .into_iter()
+            .map(|(_, node_index)| node_index)
+            .filter(|node_index| self.graph.contains_node(*node_index))
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// 包含 `pos` 的可见节点，按索引排序
+    pub fn nodes_at(&self, pos: Pos2) -> Vec<NodeIndex> {
+        let mut nodes: Vec<NodeIndex> = self
+            .spatial_index
+            .nodes
+            .query_point(pos)
+            .into_iter()
+            .map(|(_, node_index)| node_index)
+            .filter(|node_index| {
+                // 索引在这一帧开始时更新，再用当前位置确认
+                self.node_rect(*node_index)
+                    .is_some_and(|rect| rect.contains(pos))
+            })
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// 包围盒与 `rect` 相交的可见边，按索引排序
+    pub fn edges_in_rect(&self, rect: Rect) -> Vec<EdgeIndex> {
+        let mut edges: Vec<EdgeIndex> = self
+            .spatial_index
+            .edges
+            .query_rect(rect)
+            .into_iter()
+            .map(|(_, edge_index)| edge_index)
+            .filter(|edge_index| self.graph.edge_weight(*edge_index).is_some())
+            .collect();
+        edges.sort();
+        edges
+    }
+}
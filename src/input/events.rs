@@ -3,6 +3,8 @@
 use egui::{Key, Modifiers, PointerButton, Pos2, Vec2};
 use petgraph::graph::{EdgeIndex, NodeIndex};
 
+use crate::ui::bezier::DragType;
+
 #[derive(Debug, Clone)]
 pub enum InputEvent {
     /// 一次性事件 - 只触发一次
@@ -81,10 +83,18 @@ pub enum InputTarget {
     /// 特定边
     Edge(EdgeIndex),
 
+    /// 边的标签
+    EdgeLabel(EdgeIndex),
+
+    /// 节点的折叠按钮
+    CollapseToggle(NodeIndex),
+
     /// 贝塞尔曲线控制点
     ControlPoint {
         edge_index: EdgeIndex,
+        /// 锚点在首锚点、中间锚点、尾锚点序列中的索引
         point_index: usize,
+        drag_type: DragType,
     },
 
     /// 其他UI元素
@@ -1,8 +1,10 @@
 // 在 src/input/input_state.rs 中
 
-use egui::Pos2;
+use egui::{Pos2, Rect};
 use petgraph::graph::{EdgeIndex, NodeIndex};
 
+use crate::ui::bezier::DragType;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputState {
     /// 空闲状态 - 系统等待新的输入
@@ -24,11 +26,16 @@ pub enum InputState {
         // 用于多选拖动
         is_selection_drag: bool,
         selected_indices: Vec<NodeIndex>,
+        /// 开始拖动时被拖动节点的包围盒（画布坐标），吸附基于未吸附的位置计算
+        start_rect: Rect,
     },
 
     /// 节点编辑状态 - 用户正在编辑节点文本
     EditingNode { node_index: NodeIndex },
 
+    /// 边标签编辑状态 - 用户正在编辑边的标签
+    EditingEdge { edge_index: EdgeIndex },
+
     /// 创建边状态 - 用户正在从源节点创建一条边
     CreatingEdge {
         source_node: NodeIndex,
@@ -39,6 +46,7 @@ pub enum InputState {
     DraggingControlPoint {
         edge_index: EdgeIndex,
         point_index: usize,
+        drag_type: DragType,
         start_pos: Pos2,
     },
 
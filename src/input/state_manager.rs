@@ -3,10 +3,18 @@
 use std::collections::HashMap;
 
 use crate::{
+    canvas::DetailLevel,
+    geometry::snap::snap_rect,
     gpu_render::particle::particle_callback::ParticleCallback,
-    graph::render_info::NodeRenderInfo,
+    graph::{
+        edge::EdgeType,
+        helpers::{collapse_toggle_center, get_edge_label_render_info, COLLAPSE_TOGGLE_RADIUS},
+        render_info::NodeRenderInfo,
+    },
     input::{events::InputTarget, input_state::InputState},
+    layout::{self, align::Arrange, node_rects, node_sizes},
     resource::{CanvasStateResource, GraphResource},
+    ui::{bezier::DragType, canvas::helpers::visible_grid_size},
 };
 
 use eframe::egui_wgpu;
@@ -15,6 +23,25 @@ use petgraph::graph::{EdgeIndex, NodeIndex};
 
 use super::button_state::ButtonState;
 
+/// 边的命中距离（屏幕像素）
+const EDGE_HIT_DISTANCE: f32 = 6.0;
+
+/// 拖动节点时的吸附距离（屏幕像素）
+const SNAP_DISTANCE: f32 = 6.0;
+
+/// 贝塞尔控制点的命中半径（屏幕像素）
+const CONTROL_POINT_HIT_RADIUS: f32 = 8.0;
+
+/// 边标签超出边包围盒的最大距离（画布坐标），标签居中在边上，字号随缩放变化
+const EDGE_LABEL_SEARCH_MARGIN: f32 = 200.0;
+
+/// 撤销
+pub const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+
+/// 重做
+pub const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
 /// 存储输入处理所需的上下文数据
 #[derive(Debug)]
 pub struct InputContext {
@@ -41,6 +68,12 @@ pub struct InputContext {
 
     /// 从上一帧到当前帧的时间（秒）
     pub delta_time: f32,
+
+    /// 鼠标是否在画布上（没有被面板或弹出菜单遮挡）
+    pub pointer_on_canvas: bool,
+
+    /// 是否有输入框获得了键盘焦点
+    pub typing: bool,
 }
 
 impl InputContext {
@@ -54,6 +87,8 @@ impl InputContext {
             pressed_buttons: ButtonState::new(),
             pressed_keys: HashMap::new(),
             delta_time: 0.0,
+            pointer_on_canvas: false,
+            typing: false,
         }
     }
 
@@ -95,24 +130,121 @@ impl InputContext {
     }
 
     /// 检查鼠标是否在节点上
-    pub fn hit_test_node(&self, ui: &egui::Ui, screen_pos: Pos2) -> Option<NodeIndex> {
-        // 使用你现有的命中测试逻辑
+    pub fn hit_test_node(&self, _ui: &egui::Ui, screen_pos: Pos2) -> Option<NodeIndex> {
+        let canvas_pos = self.screen_to_canvas(screen_pos);
+        self.graph_resource
+            .read_resource(|graph| graph.nodes_at(canvas_pos).first().copied())
+    }
+
+    /// 检查鼠标是否在有子节点的节点的折叠按钮上
+    pub fn hit_test_collapse_toggle(&self, _ui: &egui::Ui, screen_pos: Pos2) -> Option<NodeIndex> {
+        // 缩小到只画色块时不显示折叠按钮
+        if self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.detail_level())
+            == DetailLevel::Dots
+        {
+            return None;
+        }
+        let canvas_pos = self.screen_to_canvas(screen_pos);
+        // 折叠按钮在节点右侧，按按钮的范围扩大查询区域
+        let search_rect =
+            Rect::from_center_size(canvas_pos, Vec2::splat(4.0 * COLLAPSE_TOGGLE_RADIUS + 8.0));
         self.graph_resource.read_resource(|graph| {
-            graph.graph.node_indices().find(|&node_index| {
-                let node_render_info: Option<NodeRenderInfo> = ui
-                    .ctx()
-                    .data(|d| d.get_temp(Id::new(node_index.index().to_string())));
-
-                if let Some(node_render_info) = node_render_info {
-                    let node_screen_rect =
-                        self.canvas_state_resource.read_resource(|canvas_state| {
-                            canvas_state.to_screen_rect(node_render_info.canvas_rect)
-                        });
-
-                    return node_screen_rect.contains(screen_pos);
-                }
-                false
-            })
+            graph
+                .nodes_in_rect(search_rect)
+                .into_iter()
+                .find(|&node_index| {
+                    graph.node_rect(node_index).is_some_and(|rect| {
+                        let center = collapse_toggle_center(rect);
+                        center.distance(canvas_pos) <= COLLAPSE_TOGGLE_RADIUS
+                            && graph.shows_collapse_toggle(node_index)
+                    })
+                })
+        })
+    }
+
+    /// 检查鼠标是否在边的标签上
+    pub fn hit_test_edge_label(&self, ui: &egui::Ui, screen_pos: Pos2) -> Option<EdgeIndex> {
+        let canvas_pos = self.screen_to_canvas(screen_pos);
+        // 标签中心在边上，只需要检查包围盒在标签范围内的边
+        let search_rect =
+            Rect::from_center_size(canvas_pos, Vec2::splat(2.0 * EDGE_LABEL_SEARCH_MARGIN));
+        self.graph_resource.read_resource(|graph| {
+            graph
+                .edges_in_rect(search_rect)
+                .into_iter()
+                .find(|&edge_index| {
+                    get_edge_label_render_info(edge_index, ui.ctx())
+                        .is_some_and(|render_info| render_info.canvas_rect.contains(canvas_pos))
+                })
+        })
+    }
+
+    /// 检查鼠标是否在贝塞尔边的控制柄或中间锚点上
+    pub fn hit_test_control_point(&self, screen_pos: Pos2) -> Option<InputTarget> {
+        let (canvas_pos, scaling, detail_level) =
+            self.canvas_state_resource.read_resource(|canvas_state| {
+                (
+                    canvas_state.to_canvas(screen_pos),
+                    canvas_state.transform.scaling,
+                    canvas_state.detail_level(),
+                )
+            });
+        // 控制柄和锚点只在完整细节下绘制，看不见时不能拖动
+        if detail_level != DetailLevel::Full {
+            return None;
+        }
+        let hit_radius = CONTROL_POINT_HIT_RADIUS / scaling;
+
+        let search_rect = Rect::from_center_size(canvas_pos, Vec2::splat(2.0 * hit_radius));
+
+        self.graph_resource.read_resource(|graph| {
+            graph
+                .edges_in_rect(search_rect)
+                .into_iter()
+                .find_map(|edge_index| {
+                    if graph.edge_type_of(edge_index)? != EdgeType::Bezier {
+                        return None;
+                    }
+                    let edge = graph.get_edge(edge_index)?;
+                    let (point_index, drag_type) = edge
+                        .bezier_edge
+                        .hit_test_control_point(canvas_pos, hit_radius)?;
+                    Some(InputTarget::ControlPoint {
+                        edge_index,
+                        point_index,
+                        drag_type,
+                    })
+                })
+        })
+    }
+
+    /// 检查鼠标是否在边上，返回距离最近的边
+    pub fn hit_test_edge(&self, screen_pos: Pos2) -> Option<EdgeIndex> {
+        let (canvas_pos, scaling) = self.canvas_state_resource.read_resource(|canvas_state| {
+            (
+                canvas_state.to_canvas(screen_pos),
+                canvas_state.transform.scaling,
+            )
+        });
+        // 命中距离按屏幕像素计算，换算到画布坐标
+        let hit_distance = EDGE_HIT_DISTANCE / scaling;
+
+        let search_rect = Rect::from_center_size(canvas_pos, Vec2::splat(2.0 * hit_distance));
+
+        self.graph_resource.read_resource(|graph| {
+            graph
+                .edges_in_rect(search_rect)
+                .into_iter()
+                .filter_map(|edge_index| {
+                    let edge = graph.get_edge(edge_index)?;
+                    let edge_type = graph.edge_type_of(edge_index)?;
+                    let distance = edge.distance_to(&edge_type, canvas_pos);
+                    (distance <= hit_distance).then_some((edge_index, distance))
+                })
+                .min_by(|(_, d1), (_, d2)| d1.partial_cmp(d2).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(edge_index, _)| edge_index)
         })
     }
 
@@ -140,6 +272,9 @@ pub struct InputStateManager {
 
     /// 上一次记录的输入目标
     pub last_target: Option<InputTarget>,
+
+    /// 拖动节点时显示的对齐参考线（画布坐标）
+    pub alignment_guides: Vec<[Pos2; 2]>,
 }
 
 impl InputStateManager {
@@ -148,6 +283,7 @@ impl InputStateManager {
             current_state: InputState::Idle,
             context: InputContext::new(graph_resource, canvas_state_resource),
             last_target: None,
+            alignment_guides: Vec::new(),
         }
     }
 
@@ -162,9 +298,11 @@ impl InputStateManager {
     }
 
     /// 每帧更新输入状态
-    pub fn update(&mut self, ui: &mut egui::Ui, _response: &egui::Response) {
+    pub fn update(&mut self, ui: &mut egui::Ui, response: &egui::Response) {
         // 更新上下文
         self.context.update(ui);
+        self.context.pointer_on_canvas = ui.rect_contains_pointer(response.rect);
+        self.context.typing = ui.ctx().wants_keyboard_input();
 
         // 处理输入事件，获取当前输入目标
         let target = self.determine_target(ui);
@@ -182,12 +320,13 @@ impl InputStateManager {
 
     /// 处理可能触发状态转换的一次性事件
     fn handle_one_shot_events(&mut self, ui: &mut egui::Ui, target: &InputTarget) {
-        // 检查鼠标点击
-        if ui.input(|i| i.pointer.button_pressed(PointerButton::Primary)) {
+        // 检查鼠标点击，面板和菜单上的点击不属于画布
+        let pointer_on_canvas = self.context.pointer_on_canvas;
+        if pointer_on_canvas && ui.input(|i| i.pointer.button_pressed(PointerButton::Primary)) {
             self.handle_primary_button_press(ui, target);
         }
 
-        if ui.input(|i| i.pointer.button_pressed(PointerButton::Secondary)) {
+        if pointer_on_canvas && ui.input(|i| i.pointer.button_pressed(PointerButton::Secondary)) {
             self.handle_secondary_button_press(ui, target);
         }
 
@@ -200,8 +339,9 @@ impl InputStateManager {
             self.handle_secondary_button_release(ui, target);
         }
 
-        // 检查键盘按键
-        if ui.input(|i| i.key_pressed(Key::Space)) {
+        // 检查键盘按键，输入框获得焦点时快捷键交给输入框处理
+        let typing = self.context.typing;
+        if !typing && ui.input(|i| i.key_pressed(Key::Space)) {
             self.handle_space_key_press();
         }
         if ui.input(|i| i.key_released(Key::Space)) {
@@ -212,12 +352,42 @@ impl InputStateManager {
             self.handle_escape_key();
         }
 
-        if ui.input(|i| i.key_pressed(Key::Delete) || i.key_pressed(Key::Backspace)) {
+        if !typing && ui.input(|i| i.key_pressed(Key::Delete) || i.key_pressed(Key::Backspace)) {
             self.handle_delete_key();
         }
 
+        if !typing && ui.input(|i| i.key_pressed(Key::F2)) {
+            self.handle_rename_key();
+        }
+
+        // 思维导图：Tab 添加子节点，Enter 添加兄弟节点
+        if !typing && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Tab)) {
+            self.handle_mind_map_key(false);
+        }
+        if !typing && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter)) {
+            self.handle_mind_map_key(true);
+        }
+
+        // Alt 组合键对齐、分布选中的节点
+        if !typing {
+            for arrange in Arrange::ALL {
+                if ui.input_mut(|i| i.consume_shortcut(&arrange.shortcut())) {
+                    self.handle_arrange_key(arrange);
+                }
+            }
+        }
+
+        // 撤销的快捷键不区分 Shift，先检查重做
+        if !typing && ui.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+            self.handle_undo_redo_key(true);
+        } else if !typing && ui.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+            self.handle_undo_redo_key(false);
+        }
+
         // 检查双击
-        if ui.input(|i| i.pointer.button_double_clicked(PointerButton::Primary)) {
+        if pointer_on_canvas
+            && ui.input(|i| i.pointer.button_double_clicked(PointerButton::Primary))
+        {
             self.handle_double_click(ui, target);
         }
     }
@@ -235,13 +405,13 @@ impl InputStateManager {
 
         // 处理滚动
         let scroll_delta = ui.input(|i| i.smooth_scroll_delta);
-        if scroll_delta != Vec2::ZERO {
+        if self.context.pointer_on_canvas && scroll_delta != Vec2::ZERO {
             self.handle_scroll(scroll_delta);
         }
 
         // 处理缩放
         let zoom_delta = ui.input(|i: &egui::InputState| i.zoom_delta());
-        if zoom_delta != 1.0 {
+        if self.context.pointer_on_canvas && zoom_delta != 1.0 {
             self.handle_zoom(zoom_delta);
         }
     }
@@ -250,15 +420,41 @@ impl InputStateManager {
     fn handle_state_specific_updates(&mut self, ui: &mut egui::Ui) {
         match &self.current_state {
             InputState::Idle => {
-                ui.ctx().set_cursor_icon(egui::CursorIcon::Default);
+                if matches!(self.last_target, Some(InputTarget::ControlPoint { .. })) {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+                } else {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::Default);
+                }
                 self.context.graph_resource.with_resource(|graph| {
                     graph.set_editing_node(None);
+                    graph.set_editing_edge(None);
                 });
             }
+            InputState::EditingNode { .. } => {
+                // 节点按 Ctrl+Enter 结束编辑后回到空闲状态
+                if self
+                    .context
+                    .graph_resource
+                    .read_resource(|graph| graph.get_editing_node().is_none())
+                {
+                    self.transition_to(InputState::Idle);
+                }
+            }
+            InputState::EditingEdge { .. } => {
+                // 标签输入框失去焦点（例如按下回车）时结束编辑
+                if self
+                    .context
+                    .graph_resource
+                    .read_resource(|graph| graph.get_editing_edge().is_none())
+                {
+                    self.transition_to(InputState::Idle);
+                }
+            }
             InputState::Panning {
                 last_cursor_pos: _,
                 dragging: _,
-            } => {
+            }
+            | InputState::DraggingControlPoint { .. } => {
                 ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
             }
             InputState::Selecting {
@@ -276,6 +472,9 @@ impl InputStateManager {
                 // 绘制选择框
                 self.draw_selection_rect(ui, start, current);
             }
+            InputState::DraggingNode { .. } => {
+                self.draw_alignment_guides(ui);
+            }
             InputState::CreatingEdge {
                 source_node,
                 current_cursor_pos,
@@ -292,13 +491,24 @@ impl InputStateManager {
     fn determine_target(&self, ui: &egui::Ui) -> InputTarget {
         let cursor_pos = ui.input(|i| i.pointer.hover_pos()).unwrap_or(Pos2::ZERO);
 
-        // 首先检查节点（优先级最高）
+        // 首先检查节点（优先级最高），折叠按钮画在节点旁边
+        if let Some(node_index) = self.context.hit_test_collapse_toggle(ui, cursor_pos) {
+            return InputTarget::CollapseToggle(node_index);
+        }
         if let Some(node_index) = self.context.hit_test_node(ui, cursor_pos) {
             return InputTarget::Node(node_index);
         }
 
-        // 检查边和控制点...
-        // （这里可以添加你特定的边和控制点检测逻辑）
+        // 检查边和控制点，标签和控制点优先于边
+        if let Some(edge_index) = self.context.hit_test_edge_label(ui, cursor_pos) {
+            return InputTarget::EdgeLabel(edge_index);
+        }
+        if let Some(control_point) = self.context.hit_test_control_point(cursor_pos) {
+            return control_point;
+        }
+        if let Some(edge_index) = self.context.hit_test_edge(cursor_pos) {
+            return InputTarget::Edge(edge_index);
+        }
 
         // 默认为画布
         InputTarget::Canvas
@@ -315,6 +525,9 @@ impl InputStateManager {
                 ) {
                     return;
                 }
+                if matches!(self.current_state, InputState::EditingEdge { .. }) {
+                    self.transition_to(InputState::Idle);
+                }
                 // 点击节点 - 开始拖动或选择
                 let shift_pressed = ui.input(|i| i.modifiers.shift);
 
@@ -335,12 +548,14 @@ impl InputStateManager {
                         .context
                         .graph_resource
                         .read_resource(|graph| graph.get_selected_nodes());
+                    let start_rect = self.nodes_bounds(&selected_nodes);
 
                     self.transition_to(InputState::DraggingNode {
                         node_index: *node_index,
                         start_pos: self.context.current_mouse_pos,
                         is_selection_drag: true,
                         selected_indices: selected_nodes,
+                        start_rect,
                     });
                 } else {
                     // 新的选择
@@ -350,20 +565,77 @@ impl InputStateManager {
                     });
 
                     // 开始拖动节点
+                    let start_rect = self.nodes_bounds(&[*node_index]);
                     self.transition_to(InputState::DraggingNode {
                         node_index: *node_index,
                         start_pos: self.context.current_mouse_pos,
                         is_selection_drag: false,
                         selected_indices: vec![*node_index],
+                        start_rect,
+                    });
+                }
+            }
+            InputTarget::ControlPoint {
+                edge_index,
+                point_index,
+                drag_type,
+            } => {
+                // Alt + 点击中间锚点 - 删除锚点
+                if *drag_type == DragType::Anchor && ui.input(|i| i.modifiers.alt) {
+                    self.context.graph_resource.with_resource(|graph| {
+                        graph.remove_bezier_anchor(*edge_index, *point_index);
                     });
+                    return;
                 }
+                // 点击控制点 - 开始拖拽
+                self.transition_to(InputState::DraggingControlPoint {
+                    edge_index: *edge_index,
+                    point_index: *point_index,
+                    drag_type: *drag_type,
+                    start_pos: self.context.current_mouse_pos,
+                });
+            }
+            InputTarget::Edge(edge_index) | InputTarget::EdgeLabel(edge_index) => {
+                if self.current_state
+                    == (InputState::EditingEdge {
+                        edge_index: *edge_index,
+                    })
+                {
+                    return;
+                }
+                if matches!(
+                    self.current_state,
+                    InputState::EditingNode { .. } | InputState::EditingEdge { .. }
+                ) {
+                    self.transition_to(InputState::Idle);
+                }
+                // 点击边 - 选择边，按住 Shift 时加入现有选择
+                let shift_pressed = ui.input(|i| i.modifiers.shift);
+                self.context.graph_resource.with_resource(|graph| {
+                    if !shift_pressed {
+                        graph.selected.clear();
+                    }
+                    graph.select_edge(*edge_index);
+                });
+            }
+            InputTarget::CollapseToggle(node_index) => {
+                if matches!(
+                    self.current_state,
+                    InputState::EditingNode { .. } | InputState::EditingEdge { .. }
+                ) {
+                    self.transition_to(InputState::Idle);
+                }
+                let sizes = self.context.graph_resource.read_resource(node_sizes);
+                self.context.graph_resource.with_resource(|graph| {
+                    graph.toggle_collapsed(*node_index, &sizes);
+                });
             }
             InputTarget::Canvas => {
                 // self.context.graph_resource.with_resource(|graph| {
                 //     graph.selected.clear();
                 // });
                 match &self.current_state {
-                    InputState::EditingNode { node_index: _ } => {
+                    InputState::EditingNode { node_index: _ } | InputState::EditingEdge { .. } => {
                         self.transition_to(InputState::Idle);
                     }
                     _ => {
@@ -441,7 +713,18 @@ impl InputStateManager {
                 }
             }
             InputState::DraggingNode { .. } => {
-                // 结束节点拖动
+                // 结束节点拖动，整个拖动过程作为一个撤销步骤
+                self.context.graph_resource.with_resource(|graph| {
+                    graph.history.seal();
+                });
+                self.alignment_guides.clear();
+                self.transition_to(InputState::Idle);
+            }
+            InputState::DraggingControlPoint { .. } => {
+                // 结束控制点拖拽，整个拖拽过程作为一个撤销步骤
+                self.context.graph_resource.with_resource(|graph| {
+                    graph.history.seal();
+                });
                 self.transition_to(InputState::Idle);
             }
             InputState::Selecting {
@@ -520,35 +803,61 @@ impl InputStateManager {
                 }
             }
             InputState::DraggingNode {
-                node_index,
-                start_pos: _,
-                is_selection_drag,
+                start_pos,
                 selected_indices,
+                start_rect,
+                ..
+            } => {
+                // 由鼠标的总位移得到未吸附的位置，再吸附到网格或参考线
+                let (scaling, snap_to_grid, smart_guides) = self
+                    .context
+                    .canvas_state_resource
+                    .read_resource(|s| (s.transform.scaling, s.snap_to_grid, s.smart_guides));
+                let raw_rect =
+                    start_rect.translate((self.context.current_mouse_pos - *start_pos) / scaling);
+                // 按住 Ctrl 临时关闭吸附
+                let snapping_disabled = ui.input(|i| i.modifiers.command);
+                let target_rect = if snapping_disabled || !(snap_to_grid || smart_guides) {
+                    self.alignment_guides.clear();
+                    raw_rect
+                } else {
+                    let others = self.snap_candidates(ui, selected_indices);
+                    let grid = snap_to_grid.then(|| visible_grid_size(scaling));
+                    let snap = snap_rect(
+                        raw_rect,
+                        &others,
+                        SNAP_DISTANCE / scaling,
+                        smart_guides,
+                        grid,
+                    );
+                    self.alignment_guides = snap.guides;
+                    raw_rect.translate(snap.offset)
+                };
+
+                let current_rect = self.nodes_bounds(selected_indices);
+                let delta = target_rect.min - current_rect.min;
+                if delta != Vec2::ZERO {
+                    self.context.graph_resource.with_resource(|graph| {
+                        graph.move_nodes(selected_indices, delta);
+                    });
+                }
+            }
+            InputState::DraggingControlPoint {
+                edge_index,
+                point_index,
+                drag_type,
+                start_pos: _,
             } => {
-                // 计算缩放调整后的增量
                 let scaled_delta = delta
                     / self
                         .context
                         .canvas_state_resource
                         .read_resource(|s| s.transform.scaling);
 
-                if *is_selection_drag {
-                    // 移动所有选中的节点
-                    for &idx in selected_indices {
-                        self.context.graph_resource.with_resource(|graph| {
-                            if let Some(node) = graph.get_node_mut(idx) {
-                                node.position += scaled_delta;
-                            }
-                        });
-                    }
-                } else {
-                    // 只移动当前节点
-                    self.context.graph_resource.with_resource(|graph| {
-                        if let Some(node) = graph.get_node_mut(*node_index) {
-                            node.position += scaled_delta;
-                        }
-                    });
-                }
+                // 写回图中的边，保存文件时一并保存
+                self.context.graph_resource.with_resource(|graph| {
+                    graph.drag_bezier_point(*edge_index, *point_index, *drag_type, scaled_delta);
+                });
             }
             InputState::Selecting {
                 start_pos,
@@ -606,6 +915,7 @@ impl InputStateManager {
             self.context.graph_resource.with_resource(|graph| {
                 graph.selected.clear();
                 graph.set_editing_node(None);
+                graph.set_editing_edge(None);
             });
         }
     }
@@ -613,27 +923,109 @@ impl InputStateManager {
     fn handle_delete_key(&mut self) {
         if matches!(
             self.current_state,
-            InputState::EditingNode { node_index: _ }
+            InputState::EditingNode { node_index: _ } | InputState::EditingEdge { .. }
         ) {
             return;
         }
-        // 删除选中的节点
+        // 删除选中的节点或边
         self.context.graph_resource.with_resource(|graph| {
-            let nodes_to_remove =
-                if let crate::graph::selection::GraphSelection::Node(nodes) = &graph.selected {
-                    nodes.clone() // 克隆节点列表
-                } else {
-                    Vec::new()
-                };
-
-            for node_index in nodes_to_remove {
-                graph.remove_node(node_index);
+            match graph.selected.clone() {
+                crate::graph::selection::GraphSelection::Node(nodes) => {
+                    graph.remove_nodes(&nodes);
+                }
+                crate::graph::selection::GraphSelection::Edge(edges) => {
+                    graph.remove_edges(&edges);
+                }
+                crate::graph::selection::GraphSelection::None => {}
             }
 
             graph.selected.clear();
         });
     }
 
+    /// 按 F2 编辑唯一选中的边的标签
+    fn handle_rename_key(&mut self) {
+        if !matches!(self.current_state, InputState::Idle) {
+            return;
+        }
+        let selected_edges = self
+            .context
+            .graph_resource
+            .read_resource(|graph| graph.get_selected_edges());
+        if let [edge_index] = selected_edges[..] {
+            self.start_editing_edge(edge_index);
+        }
+    }
+
+    /// 为唯一选中的节点添加子节点或兄弟节点，并开始编辑新节点
+    fn handle_mind_map_key(&mut self, sibling: bool) {
+        if !matches!(self.current_state, InputState::Idle) {
+            return;
+        }
+        let selected_nodes = self
+            .context
+            .graph_resource
+            .read_resource(|graph| graph.get_selected_nodes());
+        let [node_index] = selected_nodes[..] else {
+            return;
+        };
+
+        let new_node_id = self
+            .context
+            .canvas_state_resource
+            .read_resource(|cs| cs.new_node_id());
+        let canvas_state_resource = self.context.canvas_state_resource.clone();
+        let new_index = self.context.graph_resource.with_resource(|graph| {
+            let sizes = node_sizes(graph);
+            let new_index = if sibling {
+                graph.add_sibling_node(node_index, new_node_id, canvas_state_resource, &sizes)
+            } else {
+                graph.add_child_node(node_index, new_node_id, canvas_state_resource, &sizes)
+            }?;
+            graph.selected.clear();
+            graph.select_node(new_index);
+            graph.set_editing_node(Some(new_index));
+            Some(new_index)
+        });
+        if let Some(node_index) = new_index {
+            self.transition_to(InputState::EditingNode { node_index });
+        }
+    }
+
+    fn handle_arrange_key(&mut self, arrange: Arrange) {
+        if !matches!(self.current_state, InputState::Idle) {
+            return;
+        }
+        self.context.graph_resource.with_resource(|graph| {
+            let rects = node_rects(graph, &graph.get_selected_nodes());
+            graph.arrange_nodes(&rects, arrange);
+        });
+    }
+
+    fn start_editing_edge(&mut self, edge_index: EdgeIndex) {
+        self.context.graph_resource.with_resource(|graph| {
+            graph.selected.clear();
+            graph.select_edge(edge_index);
+            graph.set_editing_node(None);
+            graph.set_editing_edge(Some(edge_index));
+        });
+        self.transition_to(InputState::EditingEdge { edge_index });
+    }
+
+    fn handle_undo_redo_key(&mut self, redo: bool) {
+        // 编辑文本时交给文本框自身的撤销
+        if !matches!(self.current_state, InputState::Idle) {
+            return;
+        }
+        self.context.graph_resource.with_resource(|graph| {
+            if redo {
+                graph.redo();
+            } else {
+                graph.undo();
+            }
+        });
+    }
+
     fn handle_double_click(&mut self, ui: &mut egui::Ui, target: &InputTarget) {
         match target {
             InputTarget::Node(node_index) => {
@@ -645,6 +1037,35 @@ impl InputStateManager {
                     node_index: *node_index,
                 });
             }
+            InputTarget::Edge(edge_index) => {
+                // 双击贝塞尔边插入中间锚点，Alt+双击或双击其他类型的边编辑标签
+                let canvas_pos = self
+                    .context
+                    .screen_to_canvas(self.context.current_mouse_pos);
+                let is_bezier = self.context.graph_resource.read_resource(|graph| {
+                    graph.edge_type_of(*edge_index) == Some(EdgeType::Bezier)
+                });
+                if is_bezier && !ui.input(|i| i.modifiers.alt) {
+                    self.context.graph_resource.with_resource(|graph| {
+                        graph.insert_bezier_anchor(*edge_index, canvas_pos);
+                    });
+                } else {
+                    self.start_editing_edge(*edge_index);
+                }
+            }
+            InputTarget::EdgeLabel(edge_index) => {
+                self.start_editing_edge(*edge_index);
+            }
+            InputTarget::ControlPoint {
+                edge_index,
+                point_index,
+                drag_type: DragType::Anchor,
+            } => {
+                // 双击中间锚点切换平滑/尖锐
+                self.context.graph_resource.with_resource(|graph| {
+                    graph.toggle_bezier_anchor_smooth(*edge_index, *point_index);
+                });
+            }
             InputTarget::Canvas => {
                 // 双击画布创建新节点
                 let canvas_pos = self
@@ -660,6 +1081,8 @@ impl InputStateManager {
                     position: canvas_pos,
                     text: String::new(),
                     note: String::new(),
+                    collapsed: false,
+                    width: None,
                 };
 
                 let node_index = self.context.graph_resource.with_resource(|graph| {
@@ -732,10 +1155,10 @@ impl InputStateManager {
 
         // 找出在矩形内的节点
         self.context.graph_resource.with_resource(|graph| {
-            // 首先找出新选中的节点
+            // 首先找出新选中的节点，折叠隐藏的节点不在空间索引中
             let new_selected: Vec<NodeIndex> = graph
-                .graph
-                .node_indices()
+                .nodes_in_rect(selection_rect)
+                .into_iter()
                 .filter(|&idx| {
                     if let Some(node) = graph.get_node(idx) {
                         selection_rect.contains(node.position)
@@ -771,6 +1194,45 @@ impl InputStateManager {
         self.update_selection_preview(start_pos, current_pos, add_to_selection);
     }
 
+    /// 节点当前位置的包围盒（画布坐标），尺寸取自最近一次渲染
+    fn nodes_bounds(&self, node_indices: &[NodeIndex]) -> Rect {
+        self.context.graph_resource.read_resource(|graph| {
+            layout::nodes_bounds(graph, node_indices).unwrap_or(Rect::NOTHING)
+        })
+    }
+
+    /// 可以作为对齐目标的节点：没有被拖动且在屏幕上可见
+    fn snap_candidates(&self, ui: &egui::Ui, moving: &[NodeIndex]) -> Vec<Rect> {
+        let viewport = self
+            .context
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.to_canvas_rect(ui.clip_rect()));
+        self.context.graph_resource.read_resource(|graph| {
+            graph
+                .nodes_in_rect(viewport)
+                .into_iter()
+                .filter(|node_index| !moving.contains(node_index))
+                .filter_map(|node_index| graph.node_rect(node_index))
+                .filter(|rect| viewport.intersects(*rect))
+                .collect()
+        })
+    }
+
+    fn draw_alignment_guides(&self, ui: &mut egui::Ui) {
+        let painter = ui.painter();
+        let stroke = Stroke::new(1.0, Color32::from_rgb(255, 64, 160));
+        self.context
+            .canvas_state_resource
+            .read_resource(|canvas_state| {
+                for [from, to] in &self.alignment_guides {
+                    painter.line_segment(
+                        [canvas_state.to_screen(*from), canvas_state.to_screen(*to)],
+                        stroke,
+                    );
+                }
+            });
+    }
+
     fn draw_selection_rect(&self, ui: &mut egui::Ui, start_pos: Pos2, current_pos: Pos2) {
         let rect = egui::Rect::from_two_pos(start_pos, current_pos);
         let painter = ui.painter();
@@ -899,6 +1361,8 @@ impl InputStateManager {
             position: canvas_pos,
             text: String::new(),
             note: String::new(),
+            collapsed: false,
+            width: None,
         };
 
         // 添加节点并创建边
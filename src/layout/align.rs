@@ -0,0 +1,145 @@
+use egui::{Key, KeyboardShortcut, Modifiers, Pos2, Rect, Vec2};
+use petgraph::graph::NodeIndex;
+
+/// 多选节点的对齐、分布和统一宽度命令
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrange {
+    AlignLeft,
+    AlignHorizontalCenter,
+    AlignRight,
+    AlignTop,
+    AlignVerticalCenter,
+    AlignBottom,
+    DistributeHorizontally,
+    DistributeVertically,
+    MatchWidths,
+}
+
+impl Arrange {
+    pub const ALL: [Arrange; 9] = [
+        Arrange::AlignLeft,
+        Arrange::AlignHorizontalCenter,
+        Arrange::AlignRight,
+        Arrange::AlignTop,
+        Arrange::AlignVerticalCenter,
+        Arrange::AlignBottom,
+        Arrange::DistributeHorizontally,
+        Arrange::DistributeVertically,
+        Arrange::MatchWidths,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Arrange::AlignLeft => "Align Left",
+            Arrange::AlignHorizontalCenter => "Align Horizontal Centers",
+            Arrange::AlignRight => "Align Right",
+            Arrange::AlignTop => "Align Top",
+            Arrange::AlignVerticalCenter => "Align Vertical Centers",
+            Arrange::AlignBottom => "Align Bottom",
+            Arrange::DistributeHorizontally => "Distribute Horizontally",
+            Arrange::DistributeVertically => "Distribute Vertically",
+            Arrange::MatchWidths => "Match Widths",
+        }
+    }
+
+    pub fn shortcut(self) -> KeyboardShortcut {
+        let alt = Modifiers::ALT;
+        let alt_shift = Modifiers::ALT | Modifiers::SHIFT;
+        match self {
+            Arrange::AlignLeft => KeyboardShortcut::new(alt, Key::A),
+            Arrange::AlignHorizontalCenter => KeyboardShortcut::new(alt, Key::H),
+            Arrange::AlignRight => KeyboardShortcut::new(alt, Key::D),
+            Arrange::AlignTop => KeyboardShortcut::new(alt, Key::W),
+            Arrange::AlignVerticalCenter => KeyboardShortcut::new(alt, Key::V),
+            Arrange::AlignBottom => KeyboardShortcut::new(alt, Key::S),
+            Arrange::DistributeHorizontally => KeyboardShortcut::new(alt_shift, Key::H),
+            Arrange::DistributeVertically => KeyboardShortcut::new(alt_shift, Key::V),
+            Arrange::MatchWidths => KeyboardShortcut::new(alt_shift, Key::W),
+        }
+    }
+
+    /// 至少需要选中的节点数
+    pub fn min_nodes(self) -> usize {
+        match self {
+            Arrange::DistributeHorizontally | Arrange::DistributeVertically => 3,
+            _ => 2,
+        }
+    }
+
+    /// 菜单中在这一项之前加分隔线
+    pub fn starts_group(self) -> bool {
+        matches!(
+            self,
+            Arrange::AlignTop | Arrange::DistributeHorizontally | Arrange::MatchWidths
+        )
+    }
+}
+
+/// 计算对齐或分布后节点新的左上角位置，`MatchWidths` 不移动节点
+pub fn arrange_positions(rects: &[(NodeIndex, Rect)], arrange: Arrange) -> Vec<(NodeIndex, Pos2)> {
+    if rects.len() < arrange.min_nodes() {
+        return Vec::new();
+    }
+    let bounds = rects
+        .iter()
+        .fold(Rect::NOTHING, |bounds, (_, rect)| bounds.union(*rect));
+    let align = |target: fn(&Rect, &Rect) -> Pos2| {
+        rects
+            .iter()
+            .map(|(node_index, rect)| (*node_index, target(rect, &bounds)))
+            .collect()
+    };
+
+    match arrange {
+        Arrange::AlignLeft => align(|rect, bounds| Pos2::new(bounds.min.x, rect.min.y)),
+        Arrange::AlignHorizontalCenter => {
+            align(|rect, bounds| Pos2::new(bounds.center().x - rect.width() / 2.0, rect.min.y))
+        }
+        Arrange::AlignRight => {
+            align(|rect, bounds| Pos2::new(bounds.max.x - rect.width(), rect.min.y))
+        }
+        Arrange::AlignTop => align(|rect, bounds| Pos2::new(rect.min.x, bounds.min.y)),
+        Arrange::AlignVerticalCenter => {
+            align(|rect, bounds| Pos2::new(rect.min.x, bounds.center().y - rect.height() / 2.0))
+        }
+        Arrange::AlignBottom => {
+            align(|rect, bounds| Pos2::new(rect.min.x, bounds.max.y - rect.height()))
+        }
+        Arrange::DistributeHorizontally => distribute(rects, Vec2::X),
+        Arrange::DistributeVertically => distribute(rects, Vec2::Y),
+        Arrange::MatchWidths => Vec::new(),
+    }
+}
+
+/// 首尾两个节点不动，中间的节点按顺序排开，使相邻节点的间距相等
+fn distribute(rects: &[(NodeIndex, Rect)], axis: Vec2) -> Vec<(NodeIndex, Pos2)> {
+    let along = |p: Pos2| p.to_vec2().dot(axis);
+    let mut sorted = rects.to_vec();
+    sorted.sort_by(|(_, a), (_, b)| along(a.center()).total_cmp(&along(b.center())));
+
+    let start = sorted
+        .iter()
+        .map(|(_, r)| along(r.min))
+        .fold(f32::INFINITY, f32::min);
+    let end = sorted
+        .iter()
+        .map(|(_, r)| along(r.max))
+        .fold(f32::NEG_INFINITY, f32::max);
+    let total: f32 = sorted
+        .iter()
+        .map(|(_, r)| along(r.max) - along(r.min))
+        .sum();
+    // 节点比可用空间更宽时间距为负，节点会相互重叠但依然等距
+    let gap = (end - start - total) / (sorted.len() - 1) as f32;
+
+    let mut cursor = start;
+    sorted
+        .into_iter()
+        .map(|(node_index, rect)| {
+            // 只沿分布方向移动
+            let offset = (cursor - along(rect.min)) * axis;
+            cursor += along(rect.max) - along(rect.min) + gap;
+            (node_index, rect.min + offset)
+        })
+        .collect()
+}
@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Pos2, Vec2};
+use petgraph::graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::graph::{edge::Edge, node::Node};
+
+use super::DEFAULT_NODE_SIZE;
+
+/// 力导向布局参数
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ForceLayoutConfig {
+    /// 迭代次数
+    pub iterations: usize,
+    /// 相连节点边框之间的理想距离
+    pub ideal_distance: f32,
+    /// 斥力系数
+    pub repulsion: f32,
+    /// 弹簧（引力）系数
+    pub attraction: f32,
+    /// 向中心聚拢的系数，避免不连通的部分飘远
+    pub gravity: f32,
+    /// 是否以动画方式移动节点
+    pub animate: bool,
+}
+
+impl Default for ForceLayoutConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 300,
+            ideal_distance: 80.0,
+            repulsion: 1.0,
+            attraction: 1.0,
+            gravity: 0.05,
+            animate: true,
+        }
+    }
+}
+
+/// 计算力导向布局（Fruchterman–Reingold），返回节点新的左上角位置
+///
+/// 只移动 `movable` 中的节点，其余节点保持不动但仍然参与受力计算。
+/// 节点间距按节点矩形的边界计算，较宽的节点不会互相重叠。
+pub fn force_layout(
+    graph: &StableGraph<Node, Edge>,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    movable: &[NodeIndex],
+    config: &ForceLayoutConfig,
+) -> Vec<(NodeIndex, Pos2)> {
+    let movable_set: HashSet<NodeIndex> = movable.iter().copied().collect();
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    let index_of: HashMap<NodeIndex, usize> =
+        nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+    let size_of =
+        |node_index: NodeIndex| sizes.get(&node_index).copied().unwrap_or(DEFAULT_NODE_SIZE);
+    // 用矩形外接圆半径近似节点大小
+    let radii: Vec<f32> = nodes.iter().map(|n| size_of(*n).length() / 2.0).collect();
+    let mut centers: Vec<Pos2> = nodes
+        .iter()
+        .map(|n| graph[*n].position + size_of(*n) / 2.0)
+        .collect();
+    let is_movable: Vec<bool> = nodes.iter().map(|n| movable_set.contains(n)).collect();
+
+    // 位置重合的节点稍微错开，否则无法计算斥力方向
+    for i in 0..centers.len() {
+        for j in 0..i {
+            if (centers[i] - centers[j]).length_sq() < 1e-2 {
+                let angle = i as f32 * 2.399_963; // 黄金角
+                centers[i] += Vec2::angled(angle) * 1.0;
+            }
+        }
+    }
+
+    let edges: Vec<(usize, usize)> = graph
+        .edge_references()
+        .filter(|edge| edge.source() != edge.target())
+        .map(|edge| (index_of[&edge.source()], index_of[&edge.target()]))
+        .collect();
+
+    let movable_count = is_movable.iter().filter(|m| **m).count();
+    if movable_count == 0 {
+        return Vec::new();
+    }
+    let centroid = |centers: &[Pos2]| {
+        let sum = centers
+            .iter()
+            .zip(is_movable.iter())
+            .filter(|(_, m)| **m)
+            .fold(Vec2::ZERO, |acc, (c, _)| acc + c.to_vec2());
+        (sum / movable_count as f32).to_pos2()
+    };
+    let start_centroid = centroid(&centers);
+
+    let k = config.ideal_distance.max(1.0);
+    let mut temperature = k;
+    let cooling = temperature / config.iterations.max(1) as f32;
+
+    for _ in 0..config.iterations {
+        let mut displacement = vec![Vec2::ZERO; centers.len()];
+
+        // 斥力
+        for i in 0..centers.len() {
+            for j in (i + 1)..centers.len() {
+                if !is_movable[i] && !is_movable[j] {
+                    continue;
+                }
+                let delta = centers[i] - centers[j];
+                let distance = delta.length().max(0.01);
+                let gap = (distance - radii[i] - radii[j]).max(1.0);
+                let force = config.repulsion * k * k / gap;
+                let push = delta / distance * force;
+                displacement[i] += push;
+                displacement[j] -= push;
+            }
+        }
+
+        // 引力
+        for &(source, target) in &edges {
+            let delta = centers[target] - centers[source];
+            let distance = delta.length().max(0.01);
+            let gap = (distance - radii[source] - radii[target]).max(0.0);
+            let force = config.attraction * gap * gap / k;
+            let pull = delta / distance * force;
+            displacement[source] += pull;
+            displacement[target] -= pull;
+        }
+
+        // 向中心聚拢
+        let center = centroid(&centers);
+        for i in 0..centers.len() {
+            if is_movable[i] {
+                displacement[i] += (center - centers[i]) * config.gravity;
+            }
+        }
+
+        // 用温度限制每次移动的距离，逐步冷却
+        for i in 0..centers.len() {
+            if !is_movable[i] {
+                continue;
+            }
+            let length = displacement[i].length();
+            if length > 0.0 {
+                centers[i] += displacement[i] / length * length.min(temperature);
+            }
+        }
+        temperature = (temperature - cooling).max(0.5);
+    }
+
+    // 保持被布局部分的重心不变
+    let shift = start_centroid - centroid(&centers);
+    nodes
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| is_movable[*i])
+        .map(|(i, n)| (*n, centers[i] + shift - size_of(*n) / 2.0))
+        .collect()
+}
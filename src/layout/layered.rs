@@ -0,0 +1,456 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Pos2, Rect, Vec2};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::graph::{edge::Edge, node::Node};
+
+use super::DEFAULT_NODE_SIZE;
+
+/// 分层方向
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum LayerDirection {
+    TopDown,
+    LeftRight,
+}
+
+impl LayerDirection {
+    /// 从上一层指向下一层的单位向量
+    pub fn vector(&self) -> Vec2 {
+        match self {
+            LayerDirection::TopDown => Vec2::new(0.0, 1.0),
+            LayerDirection::LeftRight => Vec2::new(1.0, 0.0),
+        }
+    }
+}
+
+/// 分层布局参数
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LayeredLayoutConfig {
+    pub direction: LayerDirection,
+    /// 相邻两层之间的间距
+    pub layer_gap: f32,
+    /// 同一层相邻节点之间的间距
+    pub node_gap: f32,
+    /// 交叉最小化的迭代次数
+    pub sweeps: usize,
+    pub animate: bool,
+}
+
+impl Default for LayeredLayoutConfig {
+    fn default() -> Self {
+        Self {
+            direction: LayerDirection::TopDown,
+            layer_gap: 80.0,
+            node_gap: 30.0,
+            sweeps: 24,
+            animate: true,
+        }
+    }
+}
+
+/// 分层布局的结果
+pub struct LayeredLayout {
+    /// 节点新的左上角位置
+    pub positions: Vec<(NodeIndex, Pos2)>,
+    /// 参与布局的边，以及终点层是否在起点层之后
+    pub edges: Vec<(EdgeIndex, bool)>,
+}
+
+/// 布局中的一个位置，长边经过的每一层都有一个虚拟节点
+#[derive(Clone, Copy)]
+struct Vertex {
+    node: Option<NodeIndex>,
+    /// 沿层内方向的宽度
+    breadth: f32,
+    /// 沿分层方向的厚度
+    depth: f32,
+}
+
+/// Sugiyama 风格的分层布局：打破环、分层、减少交叉、分配坐标
+///
+/// 只对 `nodes` 及它们之间的边布局，结果的包围盒左上角与原来的包围盒对齐。
+pub fn layered_layout(
+    graph: &StableGraph<Node, Edge>,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    nodes: &[NodeIndex],
+    config: &LayeredLayoutConfig,
+) -> LayeredLayout {
+    let node_set: HashSet<NodeIndex> = nodes.iter().copied().collect();
+    let nodes: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|n| node_set.contains(n))
+        .collect();
+    if nodes.is_empty() {
+        return LayeredLayout {
+            positions: Vec::new(),
+            edges: Vec::new(),
+        };
+    }
+    let index_of: HashMap<NodeIndex, usize> =
+        nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let size_of =
+        |node_index: NodeIndex| sizes.get(&node_index).copied().unwrap_or(DEFAULT_NODE_SIZE);
+
+    let edges: Vec<(EdgeIndex, usize, usize)> = graph
+        .edge_references()
+        .filter(|edge| edge.source() != edge.target())
+        .filter_map(|edge| {
+            Some((
+                edge.id(),
+                *index_of.get(&edge.source())?,
+                *index_of.get(&edge.target())?,
+            ))
+        })
+        .collect();
+
+    // 1. 打破环：反转深度优先搜索中的回边
+    let reversed = find_back_edges(nodes.len(), &edges);
+    let dag_edges: Vec<(usize, usize)> = edges
+        .iter()
+        .zip(reversed.iter())
+        .map(|(&(_, s, t), &rev)| if rev { (t, s) } else { (s, t) })
+        .collect();
+
+    // 2. 最长路径分层
+    let layer_of = assign_layers(nodes.len(), &dag_edges);
+
+    // 3. 长边插入虚拟节点
+    let mut vertices: Vec<Vertex> = nodes
+        .iter()
+        .map(|n| {
+            let size = size_of(*n);
+            let (breadth, depth) = breadth_and_depth(config.direction, size);
+            Vertex {
+                node: Some(*n),
+                breadth,
+                depth,
+            }
+        })
+        .collect();
+    let mut vertex_layer = layer_of.clone();
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    for &(source, target) in &dag_edges {
+        let mut previous = source;
+        for layer in (layer_of[source] + 1)..layer_of[target] {
+            vertices.push(Vertex {
+                node: None,
+                breadth: 0.0,
+                depth: 0.0,
+            });
+            vertex_layer.push(layer);
+            let dummy = vertices.len() - 1;
+            links.push((previous, dummy));
+            previous = dummy;
+        }
+        links.push((previous, target));
+    }
+
+    let layer_count = vertex_layer.iter().max().map_or(0, |l| l + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (vertex, &layer) in vertex_layer.iter().enumerate() {
+        layers[layer].push(vertex);
+    }
+
+    let mut upper: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    let mut lower: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    for &(from, to) in &links {
+        lower[from].push(to);
+        upper[to].push(from);
+    }
+
+    // 4. 重心法减少交叉，保留交叉数最少的排列
+    minimize_crossings(&mut layers, &upper, &lower, config.sweeps);
+
+    // 5. 坐标分配
+    let breadth_positions =
+        assign_breadth_positions(&layers, &vertices, &upper, &lower, config.node_gap);
+
+    // 每层的厚度取该层最厚的节点，同一层的节点沿分层方向居中对齐
+    let thickness: Vec<f32> = layers
+        .iter()
+        .map(|layer| layer.iter().map(|v| vertices[*v].depth).fold(0.0, f32::max))
+        .collect();
+    let mut layer_centers = Vec::with_capacity(layer_count);
+    let mut offset = 0.0;
+    for t in &thickness {
+        layer_centers.push(offset + t / 2.0);
+        offset += t + config.layer_gap;
+    }
+
+    let mut centers: Vec<(NodeIndex, Pos2, Vec2)> = Vec::new();
+    for (layer_index, layer) in layers.iter().enumerate() {
+        for &v in layer {
+            let Some(node) = vertices[v].node else {
+                continue;
+            };
+            let depth = layer_centers[layer_index];
+            let breadth = breadth_positions[v];
+            let center = match config.direction {
+                LayerDirection::TopDown => Pos2::new(breadth, depth),
+                LayerDirection::LeftRight => Pos2::new(depth, breadth),
+            };
+            centers.push((node, center, size_of(node)));
+        }
+    }
+
+    // 对齐到原来的包围盒左上角
+    let old_bounds = nodes
+        .iter()
+        .map(|n| Rect::from_min_size(graph[*n].position, size_of(*n)))
+        .reduce(|a, b| a.union(b))
+        .unwrap_or(Rect::NOTHING);
+    let new_bounds = centers
+        .iter()
+        .map(|(_, center, size)| Rect::from_center_size(*center, *size))
+        .reduce(|a, b| a.union(b))
+        .unwrap_or(Rect::NOTHING);
+    let shift = old_bounds.min - new_bounds.min;
+
+    LayeredLayout {
+        positions: centers
+            .into_iter()
+            .map(|(node, center, size)| (node, center + shift - size / 2.0))
+            .collect(),
+        edges: edges
+            .iter()
+            .map(|&(edge_index, source, target)| (edge_index, layer_of[target] > layer_of[source]))
+            .collect(),
+    }
+}
+
+fn breadth_and_depth(direction: LayerDirection, size: Vec2) -> (f32, f32) {
+    match direction {
+        LayerDirection::TopDown => (size.x, size.y),
+        LayerDirection::LeftRight => (size.y, size.x),
+    }
+}
+
+/// 用迭代的深度优先搜索找出回边，反转它们后图中不再有环
+fn find_back_edges(node_count: usize, edges: &[(EdgeIndex, usize, usize)]) -> Vec<bool> {
+    let mut outgoing: Vec<Vec<(usize, usize)>> = vec![Vec::new(); node_count];
+    for (edge_position, &(_, source, target)) in edges.iter().enumerate() {
+        outgoing[source].push((edge_position, target));
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        OnStack,
+        Done,
+    }
+    let mut marks = vec![Mark::New; node_count];
+    let mut reversed = vec![false; edges.len()];
+
+    for root in 0..node_count {
+        if marks[root] != Mark::New {
+            continue;
+        }
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        marks[root] = Mark::OnStack;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&(edge_position, target)) = outgoing[node].get(*next) {
+                *next += 1;
+                match marks[target] {
+                    Mark::New => {
+                        marks[target] = Mark::OnStack;
+                        stack.push((target, 0));
+                    }
+                    Mark::OnStack => reversed[edge_position] = true,
+                    Mark::Done => {}
+                }
+            } else {
+                marks[node] = Mark::Done;
+                stack.pop();
+            }
+        }
+    }
+    reversed
+}
+
+/// 最长路径分层：没有入边的节点在第 0 层，其余节点在所有前驱之后
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut in_degree = vec![0usize; node_count];
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    for &(source, target) in edges {
+        outgoing[source].push(target);
+        in_degree[target] += 1;
+    }
+
+    let mut layers = vec![0usize; node_count];
+    let mut queue: Vec<usize> = (0..node_count).filter(|n| in_degree[*n] == 0).collect();
+    while let Some(node) = queue.pop() {
+        for &target in &outgoing[node] {
+            layers[target] = layers[target].max(layers[node] + 1);
+            in_degree[target] -= 1;
+            if in_degree[target] == 0 {
+                queue.push(target);
+            }
+        }
+    }
+    layers
+}
+
+/// 统计相邻两层之间的边交叉数
+fn count_crossings(layers: &[Vec<usize>], lower: &[Vec<usize>]) -> usize {
+    let mut crossings = 0;
+    for window in layers.windows(2) {
+        let position: HashMap<usize, usize> =
+            window[1].iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let mut segments: Vec<(usize, usize)> = Vec::new();
+        for (i, v) in window[0].iter().enumerate() {
+            for target in &lower[*v] {
+                if let Some(&j) = position.get(target) {
+                    segments.push((i, j));
+                }
+            }
+        }
+        for a in 0..segments.len() {
+            for b in (a + 1)..segments.len() {
+                let (s1, t1) = segments[a];
+                let (s2, t2) = segments[b];
+                if (s1 < s2 && t1 > t2) || (s1 > s2 && t1 < t2) {
+                    crossings += 1;
+                }
+            }
+        }
+    }
+    crossings
+}
+
+/// 按相邻层中邻居的平均位置重新排序，交替向下、向上扫描
+fn minimize_crossings(
+    layers: &mut [Vec<usize>],
+    upper: &[Vec<usize>],
+    lower: &[Vec<usize>],
+    sweeps: usize,
+) {
+    let mut best = layers.to_vec();
+    let mut best_crossings = count_crossings(layers, lower);
+
+    for sweep in 0..sweeps {
+        if best_crossings == 0 {
+            break;
+        }
+        let downward = sweep % 2 == 0;
+        let order: Vec<usize> = if downward {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for layer_index in order {
+            let reference = if downward {
+                layer_index - 1
+            } else {
+                layer_index + 1
+            };
+            let position: HashMap<usize, f32> = layers[reference]
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (*v, i as f32))
+                .collect();
+            let neighbors = if downward { upper } else { lower };
+            let mut keyed: Vec<(f32, usize)> = layers[layer_index]
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| {
+                    let positions: Vec<f32> = neighbors[v]
+                        .iter()
+                        .filter_map(|n| position.get(n).copied())
+                        .collect();
+                    // 没有邻居的节点保持原来的位置
+                    let key = if positions.is_empty() {
+                        i as f32
+                    } else {
+                        positions.iter().sum::<f32>() / positions.len() as f32
+                    };
+                    (key, v)
+                })
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[layer_index] = keyed.into_iter().map(|(_, v)| v).collect();
+        }
+
+        let crossings = count_crossings(layers, lower);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best = layers.to_vec();
+        }
+    }
+    layers.clone_from_slice(&best);
+}
+
+/// 沿层内方向分配坐标：先紧凑排列，再反复向邻居的平均位置靠拢并保持间距
+fn assign_breadth_positions(
+    layers: &[Vec<usize>],
+    vertices: &[Vertex],
+    upper: &[Vec<usize>],
+    lower: &[Vec<usize>],
+    node_gap: f32,
+) -> Vec<f32> {
+    let mut positions = vec![0.0f32; vertices.len()];
+    let separation =
+        |a: usize, b: usize| (vertices[a].breadth + vertices[b].breadth) / 2.0 + node_gap;
+
+    for layer in layers {
+        let mut cursor = 0.0;
+        for (i, &v) in layer.iter().enumerate() {
+            if i > 0 {
+                cursor += separation(layer[i - 1], v);
+            }
+            positions[v] = cursor;
+        }
+    }
+
+    const REFINE_ITERATIONS: usize = 8;
+    for iteration in 0..REFINE_ITERATIONS {
+        let downward = iteration % 2 == 0;
+        let order: Vec<usize> = if downward {
+            (0..layers.len()).collect()
+        } else {
+            (0..layers.len()).rev().collect()
+        };
+        for layer_index in order {
+            let layer = &layers[layer_index];
+            let neighbors = if downward { upper } else { lower };
+            let desired: Vec<f32> = layer
+                .iter()
+                .map(|&v| {
+                    let adjacent = &neighbors[v];
+                    if adjacent.is_empty() {
+                        positions[v]
+                    } else {
+                        adjacent.iter().map(|n| positions[*n]).sum::<f32>() / adjacent.len() as f32
+                    }
+                })
+                .collect();
+
+            // 在保持顺序和最小间距的前提下尽量接近期望位置：
+            // 从左到右、从右到左各推一遍，再取平均
+            let mut left = desired.clone();
+            for i in 1..layer.len() {
+                left[i] = left[i].max(left[i - 1] + separation(layer[i - 1], layer[i]));
+            }
+            let mut right = desired.clone();
+            for i in (0..layer.len().saturating_sub(1)).rev() {
+                right[i] = right[i].min(right[i + 1] - separation(layer[i], layer[i + 1]));
+            }
+            let mut merged: Vec<f32> = left
+                .iter()
+                .zip(right.iter())
+                .map(|(l, r)| (l + r) / 2.0)
+                .collect();
+            for i in 1..layer.len() {
+                merged[i] = merged[i].max(merged[i - 1] + separation(layer[i - 1], layer[i]));
+            }
+            for (i, &v) in layer.iter().enumerate() {
+                positions[v] = merged[i];
+            }
+        }
+    }
+    positions
+}
@@ -0,0 +1,91 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Pos2, Vec2};
+use petgraph::graph::NodeIndex;
+
+use crate::graph::graph_impl::Graph;
+
+use super::DEFAULT_NODE_SIZE;
+
+/// 父节点右边框到子节点左边框的距离
+pub const MIND_MAP_LEVEL_GAP: f32 = 50.0;
+
+/// 相邻兄弟子树之间的垂直间距
+pub const MIND_MAP_SIBLING_GAP: f32 = 12.0;
+
+/// 思维导图中的一棵子树
+struct Subtree {
+    node: NodeIndex,
+    size: Vec2,
+    children: Vec<Subtree>,
+    /// 整棵子树占据的高度
+    extent: f32,
+}
+
+/// 把以 `root` 为根的思维导图整理为向右展开的树，返回除根以外节点的新位置
+///
+/// 子节点按当前的纵向位置排序，折叠的子树不参与布局。
+pub fn mind_map_layout(
+    graph: &Graph,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    root: NodeIndex,
+) -> Vec<(NodeIndex, Pos2)> {
+    let Some(root_node) = graph.get_node(root) else {
+        return Vec::new();
+    };
+    let mut visited = HashSet::from([root]);
+    let tree = build_subtree(graph, sizes, root, &mut visited);
+
+    let mut positions = Vec::new();
+    let root_center_y = root_node.position.y + tree.size.y / 2.0;
+    place_children(&tree, root_node.position, root_center_y, &mut positions);
+    positions
+}
+
+fn build_subtree(
+    graph: &Graph,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    node: NodeIndex,
+    visited: &mut HashSet<NodeIndex>,
+) -> Subtree {
+    let size = sizes.get(&node).copied().unwrap_or(DEFAULT_NODE_SIZE);
+    let collapsed = graph.get_node(node).is_some_and(|n| n.collapsed);
+    let mut children = Vec::new();
+    if !collapsed {
+        for child in graph.tree_children(node) {
+            // 有多个父节点或成环时，只在第一次访问到的位置布局
+            if visited.insert(child) {
+                children.push(build_subtree(graph, sizes, child, visited));
+            }
+        }
+    }
+
+    let children_extent = children.iter().map(|c| c.extent).sum::<f32>()
+        + MIND_MAP_SIBLING_GAP * children.len().saturating_sub(1) as f32;
+    Subtree {
+        node,
+        size,
+        extent: size.y.max(children_extent),
+        children,
+    }
+}
+
+/// 子节点在父节点右侧纵向排列，整体与父节点垂直居中
+fn place_children(
+    tree: &Subtree,
+    position: Pos2,
+    center_y: f32,
+    positions: &mut Vec<(NodeIndex, Pos2)>,
+) {
+    let children_extent = tree.children.iter().map(|c| c.extent).sum::<f32>()
+        + MIND_MAP_SIBLING_GAP * tree.children.len().saturating_sub(1) as f32;
+    let x = position.x + tree.size.x + MIND_MAP_LEVEL_GAP;
+    let mut top = center_y - children_extent / 2.0;
+    for child in &tree.children {
+        let child_center_y = top + child.extent / 2.0;
+        let child_position = Pos2::new(x, child_center_y - child.size.y / 2.0);
+        positions.push((child.node, child_position));
+        place_children(child, child_position, child_center_y, positions);
+        top += child.extent + MIND_MAP_SIBLING_GAP;
+    }
+}
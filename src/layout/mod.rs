@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use egui::{Pos2, Rect, Vec2};
+use petgraph::graph::NodeIndex;
+
+use crate::graph::graph_impl::Graph;
+
+pub mod align;
+pub mod force;
+pub mod layered;
+pub mod mind_map;
+pub mod tree;
+
+/// 还没有渲染过的节点使用的默认尺寸
+pub const DEFAULT_NODE_SIZE: Vec2 = Vec2::new(60.0, 30.0);
+
+/// 布局动画的默认时长（秒）
+pub const LAYOUT_ANIMATION_DURATION: f32 = 0.5;
+
+/// 节点最近一次渲染的画布尺寸，还没有渲染过的节点使用默认尺寸
+pub fn node_sizes(graph: &Graph) -> HashMap<NodeIndex, Vec2> {
+    graph
+        .graph
+        .node_indices()
+        .map(|node_index| {
+            let size = graph.node_size(node_index).unwrap_or(DEFAULT_NODE_SIZE);
+            (node_index, size)
+        })
+        .collect()
+}
+
+/// 节点在画布上的矩形，与命中测试使用同一个尺寸来源
+pub fn node_rects(graph: &Graph, nodes: &[NodeIndex]) -> Vec<(NodeIndex, Rect)> {
+    nodes
+        .iter()
+        .filter_map(|&node_index| Some((node_index, graph.node_rect(node_index)?)))
+        .collect()
+}
+
+/// 一组节点的外接矩形，没有节点时为空
+pub fn nodes_bounds(graph: &Graph, nodes: &[NodeIndex]) -> Option<Rect> {
+    node_rects(graph, nodes)
+        .into_iter()
+        .map(|(_, rect)| rect)
+        .reduce(|bounds, rect| bounds.union(rect))
+}
+
+/// 节点从当前位置平滑移动到布局结果
+#[derive(Clone, Debug)]
+pub struct LayoutAnimation {
+    pub from: Vec<(NodeIndex, Pos2)>,
+    pub to: Vec<(NodeIndex, Pos2)>,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl LayoutAnimation {
+    pub fn new(from: Vec<(NodeIndex, Pos2)>, to: Vec<(NodeIndex, Pos2)>) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            duration: LAYOUT_ANIMATION_DURATION,
+        }
+    }
+
+    /// 推进动画，返回当前帧各节点的位置
+    pub fn step(&mut self, dt: f32) -> Vec<(NodeIndex, Pos2)> {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        let t = self.progress();
+        // 缓出曲线，结尾更柔和
+        let eased = 1.0 - (1.0 - t).powi(3);
+        self.from
+            .iter()
+            .zip(self.to.iter())
+            .map(|((node_index, from), (_, to))| (*node_index, from.lerp(*to, eased)))
+            .collect()
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+}
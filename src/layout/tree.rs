@@ -0,0 +1,321 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f32::consts::TAU;
+
+use egui::{Pos2, Vec2};
+use petgraph::graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::graph::{edge::Edge, node::Node};
+
+use super::layered::LayerDirection;
+use super::DEFAULT_NODE_SIZE;
+
+/// 树形布局参数
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TreeLayoutConfig {
+    pub direction: LayerDirection,
+    /// 相邻两层之间的间距
+    pub level_gap: f32,
+    /// 相邻子树之间的最小间距
+    pub sibling_gap: f32,
+    pub animate: bool,
+}
+
+impl Default for TreeLayoutConfig {
+    fn default() -> Self {
+        Self {
+            direction: LayerDirection::TopDown,
+            level_gap: 60.0,
+            sibling_gap: 20.0,
+            animate: true,
+        }
+    }
+}
+
+/// 径向布局参数
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RadialLayoutConfig {
+    /// 相邻两圈之间的最小间距
+    pub ring_gap: f32,
+    /// 同一圈相邻节点之间的最小间距
+    pub node_gap: f32,
+    pub animate: bool,
+}
+
+impl Default for RadialLayoutConfig {
+    fn default() -> Self {
+        Self {
+            ring_gap: 60.0,
+            node_gap: 16.0,
+            animate: true,
+        }
+    }
+}
+
+/// 从根节点出发的生成树，边不区分方向
+struct SpanningTree {
+    root: NodeIndex,
+    children: HashMap<NodeIndex, Vec<NodeIndex>>,
+    /// 按广度优先顺序排列的节点
+    order: Vec<NodeIndex>,
+    depth: HashMap<NodeIndex, usize>,
+}
+
+impl SpanningTree {
+    /// 广度优先搜索生成树，子节点按 `key` 排序以保留当前的大致顺序
+    fn new(
+        graph: &StableGraph<Node, Edge>,
+        root: NodeIndex,
+        key: impl Fn(NodeIndex) -> f32,
+    ) -> Self {
+        let mut children: HashMap<NodeIndex, Vec<NodeIndex>> = HashMap::new();
+        let mut depth = HashMap::from([(root, 0)]);
+        let mut order = Vec::new();
+        let mut visited = HashSet::from([root]);
+        let mut queue = VecDeque::from([root]);
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            let mut neighbors: Vec<NodeIndex> = graph
+                .edges_directed(node, Direction::Outgoing)
+                .map(|edge| edge.target())
+                .chain(
+                    graph
+                        .edges_directed(node, Direction::Incoming)
+                        .map(|edge| edge.source()),
+                )
+                .filter(|neighbor| visited.insert(*neighbor))
+                .collect();
+            neighbors.sort_by(|a, b| key(*a).total_cmp(&key(*b)));
+            for &neighbor in &neighbors {
+                depth.insert(neighbor, depth[&node] + 1);
+                queue.push_back(neighbor);
+            }
+            children.insert(node, neighbors);
+        }
+
+        Self {
+            root,
+            children,
+            order,
+            depth,
+        }
+    }
+
+    fn children(&self, node: NodeIndex) -> &[NodeIndex] {
+        self.children.get(&node).map_or(&[], |c| c.as_slice())
+    }
+}
+
+/// 子树在每一层的左右边界，相对于子树根节点的中心
+struct Contour {
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+/// Reingold–Tilford 风格的整齐树布局，返回与 `root` 连通的节点新的左上角位置
+///
+/// 子树之间按轮廓尽量靠拢，父节点位于第一个和最后一个子节点中间。
+/// 根节点的位置保持不变，与根不连通的节点不移动。
+pub fn tree_layout(
+    graph: &StableGraph<Node, Edge>,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    root: NodeIndex,
+    config: &TreeLayoutConfig,
+) -> Vec<(NodeIndex, Pos2)> {
+    if !graph.contains_node(root) {
+        return Vec::new();
+    }
+    let size_of = |node: NodeIndex| sizes.get(&node).copied().unwrap_or(DEFAULT_NODE_SIZE);
+    // 沿层内方向的宽度和沿分层方向的厚度
+    let extent = |node: NodeIndex| {
+        let size = size_of(node);
+        match config.direction {
+            LayerDirection::TopDown => (size.x, size.y),
+            LayerDirection::LeftRight => (size.y, size.x),
+        }
+    };
+    let tree = SpanningTree::new(graph, root, |node| {
+        let center = graph[node].position + size_of(node) / 2.0;
+        match config.direction {
+            LayerDirection::TopDown => center.x,
+            LayerDirection::LeftRight => center.y,
+        }
+    });
+
+    // 子节点中心相对于父节点中心的偏移，自底向上计算
+    let mut offsets: HashMap<NodeIndex, f32> = HashMap::new();
+    let mut contours: HashMap<NodeIndex, Contour> = HashMap::new();
+    for &node in tree.order.iter().rev() {
+        let (breadth, _) = extent(node);
+        let mut merged: Option<Contour> = None;
+        let mut child_positions = Vec::new();
+        for &child in tree.children(node) {
+            let contour = contours.remove(&child).expect("子树先于父节点计算");
+            let position = match &merged {
+                None => 0.0,
+                Some(merged) => {
+                    // 在共同的层上，新子树的左边界要在已有部分右边界之外
+                    merged
+                        .right
+                        .iter()
+                        .zip(contour.left.iter())
+                        .map(|(right, left)| right - left + config.sibling_gap)
+                        .fold(f32::NEG_INFINITY, f32::max)
+                }
+            };
+            merged = Some(match merged {
+                None => contour,
+                Some(mut merged) => {
+                    for (level, (left, right)) in
+                        contour.left.iter().zip(contour.right.iter()).enumerate()
+                    {
+                        if level < merged.right.len() {
+                            merged.right[level] = right + position;
+                        } else {
+                            merged.left.push(left + position);
+                            merged.right.push(right + position);
+                        }
+                    }
+                    merged
+                }
+            });
+            child_positions.push((child, position));
+        }
+
+        let mut contour = Contour {
+            left: vec![-breadth / 2.0],
+            right: vec![breadth / 2.0],
+        };
+        if let Some(merged) = merged {
+            // 父节点放在第一个和最后一个子节点中间
+            let first = child_positions.first().map_or(0.0, |(_, p)| *p);
+            let last = child_positions.last().map_or(0.0, |(_, p)| *p);
+            let center = (first + last) / 2.0;
+            for (child, position) in child_positions {
+                offsets.insert(child, position - center);
+            }
+            contour
+                .left
+                .extend(merged.left.iter().map(|left| left - center));
+            contour
+                .right
+                .extend(merged.right.iter().map(|right| right - center));
+        }
+        contours.insert(node, contour);
+    }
+
+    // 每层的厚度取该层最厚的节点，同一层的节点居中对齐
+    let level_count = tree.depth.values().max().map_or(0, |d| d + 1);
+    let mut thickness = vec![0.0f32; level_count];
+    for &node in &tree.order {
+        let level = tree.depth[&node];
+        thickness[level] = thickness[level].max(extent(node).1);
+    }
+    let mut level_centers = Vec::with_capacity(level_count);
+    let mut cursor = 0.0;
+    for t in &thickness {
+        level_centers.push(cursor + t / 2.0);
+        cursor += t + config.level_gap;
+    }
+
+    let root_center = graph[root].position + size_of(root) / 2.0;
+    let mut breadth_positions: HashMap<NodeIndex, f32> = HashMap::from([(root, 0.0)]);
+    let mut positions = Vec::new();
+    for &node in &tree.order {
+        let breadth = breadth_positions[&node];
+        for &child in tree.children(node) {
+            breadth_positions.insert(child, breadth + offsets[&child]);
+        }
+        let depth = level_centers[tree.depth[&node]] - level_centers[0];
+        let center = match config.direction {
+            LayerDirection::TopDown => root_center + Vec2::new(breadth, depth),
+            LayerDirection::LeftRight => root_center + Vec2::new(depth, breadth),
+        };
+        positions.push((node, center - size_of(node) / 2.0));
+    }
+    positions
+}
+
+/// 以 `root` 为圆心的径向树布局，返回与 `root` 连通的节点新的左上角位置
+///
+/// 每棵子树分到的角度与其叶子的大小成正比，每一圈的半径保证同圈节点不重叠。
+pub fn radial_layout(
+    graph: &StableGraph<Node, Edge>,
+    sizes: &HashMap<NodeIndex, Vec2>,
+    root: NodeIndex,
+    config: &RadialLayoutConfig,
+) -> Vec<(NodeIndex, Pos2)> {
+    if !graph.contains_node(root) {
+        return Vec::new();
+    }
+    let size_of = |node: NodeIndex| sizes.get(&node).copied().unwrap_or(DEFAULT_NODE_SIZE);
+    // 用矩形对角线近似节点在圆周上占据的长度
+    let footprint = |node: NodeIndex| size_of(node).length() + config.node_gap;
+    let root_center = graph[root].position + size_of(root) / 2.0;
+    let tree = SpanningTree::new(graph, root, |node| {
+        let offset = graph[node].position + size_of(node) / 2.0 - root_center;
+        offset.y.atan2(offset.x)
+    });
+
+    // 子树的权重：叶子的占地之和
+    let mut weights: HashMap<NodeIndex, f32> = HashMap::new();
+    for &node in tree.order.iter().rev() {
+        let children = tree.children(node);
+        let weight = if children.is_empty() {
+            footprint(node)
+        } else {
+            children.iter().map(|c| weights[c]).sum::<f32>()
+        };
+        weights.insert(node, weight.max(footprint(node)));
+    }
+
+    // 为每个节点分配角度范围，节点位于范围中间
+    let mut wedges: HashMap<NodeIndex, (f32, f32)> = HashMap::from([(root, (0.0, TAU))]);
+    for &node in &tree.order {
+        let (start, span) = wedges[&node];
+        let children = tree.children(node);
+        let total: f32 = children.iter().map(|c| weights[c]).sum();
+        let mut cursor = start;
+        for &child in children {
+            let child_span = span * weights[&child] / total.max(f32::EPSILON);
+            wedges.insert(child, (cursor, child_span));
+            cursor += child_span;
+        }
+    }
+
+    // 每圈的半径：既要离内圈足够远，也要让每个节点在自己的角度范围内放得下
+    let level_count = tree.depth.values().max().map_or(0, |d| d + 1);
+    let mut max_radius = vec![0.0f32; level_count];
+    let mut required = vec![0.0f32; level_count];
+    for &node in &tree.order {
+        let level = tree.depth[&node];
+        max_radius[level] = max_radius[level].max(size_of(node).length() / 2.0);
+        let (_, span) = wedges[&node];
+        required[level] = required[level].max(footprint(node) / span.max(f32::EPSILON));
+    }
+    let mut radii = vec![0.0f32; level_count];
+    for level in 1..level_count {
+        let outside_previous =
+            radii[level - 1] + max_radius[level - 1] + max_radius[level] + config.ring_gap;
+        radii[level] = outside_previous.max(required[level]);
+    }
+
+    tree.order
+        .iter()
+        .map(|&node| {
+            let (start, span) = wedges[&node];
+            let radius = radii[tree.depth[&node]];
+            let center = if node == tree.root {
+                root_center
+            } else {
+                root_center + Vec2::angled(start + span / 2.0) * radius
+            };
+            (node, center - size_of(node) / 2.0)
+        })
+        .collect()
+}
@@ -1,10 +1,14 @@
 pub mod app;
 pub mod canvas;
 pub mod colors;
+pub mod document;
+pub mod file_task;
 pub mod geometry;
 pub mod gpu_render;
 pub mod graph;
 pub mod input;
+pub mod layout;
+pub mod recovery;
 pub mod resource;
 pub mod ui;
 pub use app::TemplateApp;
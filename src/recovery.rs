@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::document::{Document, DocumentError};
+
+/// 自动保存恢复文件的间隔（秒）
+pub const AUTOSAVE_INTERVAL: f64 = 60.0;
+
+/// 与 `eframe::run_native` 使用的应用名一致，恢复文件和应用状态放在同一个目录
+pub const APP_NAME: &str = "eframe template";
+
+const RECOVERY_FILE_NAME: &str = "recovery.cnt";
+
+/// 程序异常退出后可以恢复的文档
+#[derive(Debug)]
+pub struct Recovery {
+    /// 文档原来的路径，还没有保存过时为空
+    pub document_path: Option<PathBuf>,
+    pub document: Document,
+}
+
+#[derive(serde::Serialize)]
+struct RecoveryFileRef<'a> {
+    document_path: Option<&'a Path>,
+    document: &'a Document,
+}
+
+#[derive(serde::Deserialize)]
+struct RecoveryFile {
+    document_path: Option<PathBuf>,
+    /// 按普通文档读取，旧版本的恢复文件同样经过升级
+    document: Value,
+}
+
+/// 恢复文件的位置
+pub fn recovery_path() -> Option<PathBuf> {
+    eframe::storage_dir(APP_NAME).map(|dir| dir.join(RECOVERY_FILE_NAME))
+}
+
+/// 读取上次留下的恢复文件，没有时返回空
+pub fn read_recovery() -> Option<Result<Recovery, DocumentError>> {
+    let data = std::fs::read(recovery_path()?).ok()?;
+    Some(parse_recovery(&data))
+}
+
+fn parse_recovery(data: &[u8]) -> Result<Recovery, DocumentError> {
+    let file: RecoveryFile = serde_json::from_slice(data)?;
+    Ok(Recovery {
+        document_path: file.document_path,
+        document: Document::from_value(file.document)?,
+    })
+}
+
+/// 在后台写入和清除恢复文件
+#[derive(Debug, Default)]
+pub struct Autosaver {
+    state: Arc<Mutex<AutosaveState>>,
+}
+
+#[derive(Debug, Default)]
+struct AutosaveState {
+    /// 每次清除恢复文件时加一，清除之前开始的写入不再进行
+    generation: u64,
+    /// 下一次写入的序号，按开始写入的顺序递增
+    next_sequence: u64,
+    /// 已经改名为恢复文件的最新写入的序号
+    written_sequence: Option<u64>,
+}
+
+impl Autosaver {
+    /// 在后台把文档写入恢复文件
+    ///
+    /// 多次写入可能同时进行，每次使用各自的临时文件，较早开始的写入不会覆盖较新的恢复文件。
+    pub fn write(&self, runtime: &Runtime, document_path: Option<PathBuf>, document: Document) {
+        let Some(path) = recovery_path() else {
+            return;
+        };
+        let state = self.state.clone();
+        let (started, sequence) = {
+            let mut state = state.lock().unwrap();
+            let sequence = state.next_sequence;
+            state.next_sequence += 1;
+            (state.generation, sequence)
+        };
+        runtime.spawn_blocking(move || {
+            let data = match serde_json::to_string(&RecoveryFileRef {
+                document_path: document_path.as_deref(),
+                document: &document,
+            }) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("autosave failed: {e}");
+                    return;
+                }
+            };
+            if state.lock().unwrap().generation != started {
+                return;
+            }
+            // 先在锁外写临时文件，不阻塞界面线程的清除
+            let temp_path = path.with_extension(format!("{sequence}.tmp"));
+            if let Err(e) = write_temp_file(&temp_path, data.as_bytes()) {
+                log::warn!("autosave to {} failed: {e}", temp_path.display());
+                return;
+            }
+            // 改名很快，在锁内进行，保证不会在清除之后又出现恢复文件
+            let mut state = state.lock().unwrap();
+            let current = state.generation == started
+                && state
+                    .written_sequence
+                    .is_none_or(|written| written < sequence);
+            let result = if current {
+                state.written_sequence = Some(sequence);
+                std::fs::rename(&temp_path, &path)
+            } else {
+                std::fs::remove_file(&temp_path)
+            };
+            if let Err(e) = result {
+                log::warn!("autosave to {} failed: {e}", path.display());
+            }
+        });
+    }
+
+    /// 文档已经保存或者被丢弃，删除恢复文件
+    pub fn remove(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        let Some(path) = recovery_path() else {
+            return;
+        };
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("could not remove {}: {e}", path.display());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 写入临时文件，之后改名为恢复文件，崩溃时不会留下写了一半的恢复文件
+fn write_temp_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, data)
+}
@@ -1,6 +1,12 @@
-use egui::{CursorIcon, Id, PointerButton, Pos2, Rect, Response, Sense, Shape, Stroke, Ui, Widget};
+use egui::{Id, Pos2, Rect, Response, Sense, Shape, Stroke, Ui, Vec2, Widget};
 
-use crate::{graph::anchor::BezierAnchor, resource::CanvasStateResource};
+use crate::{
+    canvas::DetailLevel,
+    colors::{edge_selected, edge_stroke},
+    geometry::distance_to_polyline,
+    graph::anchor::BezierAnchor,
+    resource::CanvasStateResource,
+};
 
 use super::helpers::draw_dashed_rect_with_offset;
 
@@ -83,6 +89,206 @@ impl BezierEdge {
         self.control_anchors = control_anchors;
         self
     }
+
+    /// 按顺序返回首锚点、中间锚点和尾锚点
+    pub fn full_anchors(&self) -> Vec<&BezierAnchor> {
+        std::iter::once(&self.source_anchor)
+            .chain(self.control_anchors.iter())
+            .chain(std::iter::once(&self.target_anchor))
+            .collect()
+    }
+
+    /// 在画布坐标下对整条曲线采样，每段采样 `samples` 次
+    pub fn sample_points(&self, samples: usize) -> Vec<Pos2> {
+        let full_anchors = self.full_anchors();
+        let mut points = Vec::with_capacity((full_anchors.len() - 1) * (samples + 1));
+        for i in 0..full_anchors.len() - 1 {
+            for step in 0..=samples {
+                let t = step as f32 / samples as f32;
+                points.push(cubic_bezier(
+                    full_anchors[i].canvas_pos,
+                    full_anchors[i].handle_out_canvas_pos,
+                    full_anchors[i + 1].handle_in_canvas_pos,
+                    full_anchors[i + 1].canvas_pos,
+                    t,
+                ));
+            }
+        }
+        points
+    }
+
+    /// 画布坐标下点到曲线的近似距离
+    pub fn distance_to(&self, canvas_pos: Pos2) -> f32 {
+        distance_to_polyline(canvas_pos, &self.sample_points(50))
+    }
+
+    fn full_anchors_mut(&mut self) -> Vec<&mut BezierAnchor> {
+        std::iter::once(&mut self.source_anchor)
+            .chain(self.control_anchors.iter_mut())
+            .chain(std::iter::once(&mut self.target_anchor))
+            .collect()
+    }
+
+    /// 检测画布坐标命中的控制柄或中间锚点，返回锚点索引和拖拽类型
+    pub fn hit_test_control_point(
+        &self,
+        canvas_pos: Pos2,
+        hit_radius: f32,
+    ) -> Option<(usize, DragType)> {
+        let full_anchors = self.full_anchors();
+        let last = full_anchors.len() - 1;
+
+        // 检测控制柄 (HandleIn)，跳过首锚点
+        for (i, anchor) in full_anchors.iter().enumerate().skip(1) {
+            if (canvas_pos - anchor.handle_in_canvas_pos).length() < hit_radius {
+                return Some((i, DragType::HandleIn));
+            }
+        }
+        // 检测控制柄 (HandleOut)，跳过尾锚点
+        for (i, anchor) in full_anchors.iter().enumerate().take(last) {
+            if (canvas_pos - anchor.handle_out_canvas_pos).length() < hit_radius {
+                return Some((i, DragType::HandleOut));
+            }
+        }
+        // 检测中间锚点，首尾锚点跟随节点
+        for (i, anchor) in full_anchors.iter().enumerate().take(last).skip(1) {
+            if (canvas_pos - anchor.canvas_pos).length() < hit_radius {
+                return Some((i, DragType::Anchor));
+            }
+        }
+
+        None
+    }
+
+    /// 找到曲线上离给定点最近的位置，返回所在段的索引和参数 t
+    pub fn nearest_point(&self, canvas_pos: Pos2) -> (usize, f32) {
+        let samples = 50;
+        let full_anchors = self.full_anchors();
+        let mut best = (0, 0.0, f32::INFINITY);
+
+        for i in 0..full_anchors.len() - 1 {
+            let p0 = full_anchors[i].canvas_pos;
+            let p1 = full_anchors[i].handle_out_canvas_pos;
+            let p2 = full_anchors[i + 1].handle_in_canvas_pos;
+            let p3 = full_anchors[i + 1].canvas_pos;
+
+            // 先粗采样，再在最近采样点附近细化
+            let mut best_t = 0.0;
+            let mut best_distance = f32::INFINITY;
+            for step in 0..=samples {
+                let t = step as f32 / samples as f32;
+                let distance = (cubic_bezier(p0, p1, p2, p3, t) - canvas_pos).length();
+                if distance < best_distance {
+                    best_distance = distance;
+                    best_t = t;
+                }
+            }
+            let step = 1.0 / samples as f32;
+            for refine in 0..=20 {
+                let t = (best_t - step + 2.0 * step * refine as f32 / 20.0).clamp(0.0, 1.0);
+                let distance = (cubic_bezier(p0, p1, p2, p3, t) - canvas_pos).length();
+                if distance < best_distance {
+                    best_distance = distance;
+                    best_t = t;
+                }
+            }
+
+            if best_distance < best.2 {
+                best = (i, best_t, best_distance);
+            }
+        }
+
+        (best.0, best.1)
+    }
+
+    /// 在第 `segment` 段的参数 `t` 处插入锚点，使用 de Casteljau 细分保持曲线形状不变。
+    /// 返回新锚点在首锚点、中间锚点、尾锚点序列中的索引
+    pub fn insert_anchor(&mut self, segment: usize, t: f32) -> usize {
+        let mut all_anchors = self.full_anchors_mut();
+        let p0 = all_anchors[segment].canvas_pos;
+        let p1 = all_anchors[segment].handle_out_canvas_pos;
+        let p2 = all_anchors[segment + 1].handle_in_canvas_pos;
+        let p3 = all_anchors[segment + 1].canvas_pos;
+
+        let p01 = p0.lerp(p1, t);
+        let p12 = p1.lerp(p2, t);
+        let p23 = p2.lerp(p3, t);
+        let p012 = p01.lerp(p12, t);
+        let p123 = p12.lerp(p23, t);
+        let split = p012.lerp(p123, t);
+
+        all_anchors[segment].handle_out_canvas_pos = p01;
+        all_anchors[segment + 1].handle_in_canvas_pos = p23;
+
+        // 细分得到的控制柄共线但长度不一定相等，平滑锚点拖动时保留各自的长度，形状不会改变
+        let anchor = BezierAnchor::new_smooth(split).with_handles(p012, p123);
+        self.control_anchors.insert(segment, anchor);
+        segment + 1
+    }
+
+    /// 删除中间锚点，首尾锚点不能删除
+    pub fn remove_anchor(&mut self, index: usize) -> bool {
+        if index == 0 || index > self.control_anchors.len() {
+            return false;
+        }
+        self.control_anchors.remove(index - 1);
+        true
+    }
+
+    /// 在平滑和尖锐之间切换中间锚点
+    pub fn toggle_anchor_smooth(&mut self, index: usize) {
+        if index == 0 || index > self.control_anchors.len() {
+            return;
+        }
+        let anchor = &mut self.control_anchors[index - 1];
+        if anchor.is_smooth {
+            anchor.set_sharp();
+        } else {
+            anchor.set_smooth();
+        }
+    }
+
+    /// 按拖拽类型移动锚点或控制柄，`delta` 为画布坐标增量
+    pub fn drag_point(&mut self, index: usize, drag_type: DragType, delta: Vec2) {
+        if drag_type == DragType::MoveBezier {
+            for anchor in self.full_anchors_mut() {
+                anchor.canvas_pos += delta;
+                anchor.handle_in_canvas_pos += delta;
+                anchor.handle_out_canvas_pos += delta;
+            }
+            return;
+        }
+
+        let mut all_anchors = self.full_anchors_mut();
+        let Some(anchor) = all_anchors.get_mut(index) else {
+            return;
+        };
+
+        match drag_type {
+            DragType::Anchor => {
+                anchor.canvas_pos += delta;
+                anchor.handle_in_canvas_pos += delta;
+                anchor.handle_out_canvas_pos += delta;
+                // 如果锚点是平滑状态，需要强制更新控制柄
+                if anchor.is_smooth {
+                    anchor.enforce_smooth();
+                }
+            }
+            DragType::HandleIn => {
+                anchor.handle_in_canvas_pos += delta;
+                if anchor.is_smooth {
+                    anchor.enforce_smooth();
+                }
+            }
+            DragType::HandleOut => {
+                anchor.handle_out_canvas_pos += delta;
+                if anchor.is_smooth {
+                    anchor.enforce_smooth_from_out();
+                }
+            }
+            DragType::None | DragType::MoveBezier => {}
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -98,8 +304,7 @@ pub enum DragType {
 pub struct BezierWidget {
     pub edge: BezierEdge,
     pub canvas_state_resource: CanvasStateResource,
-    pub dragging: DragType,
-    pub dragging_anchor_index: Option<usize>, // 拖拽锚点索引
+    pub selected: bool,
 }
 
 impl BezierWidget {
@@ -107,11 +312,37 @@ impl BezierWidget {
         BezierWidget {
             edge,
             canvas_state_resource,
-            dragging: DragType::None,
-            dragging_anchor_index: None,
+            selected: false,
         }
     }
 
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    fn stroke(&self, ui: &Ui) -> Stroke {
+        if self.selected {
+            Stroke::new(3.0, edge_selected(ui.ctx().theme()))
+        } else {
+            Stroke::new(2.0, edge_stroke(ui.ctx().theme()))
+        }
+    }
+
+    /// 当前细节层次下每段曲线细分的线段数，缩小时用更少的线段
+    fn curve_segments(&self) -> usize {
+        match self.detail_level() {
+            DetailLevel::Full => 100,
+            DetailLevel::Boxes => 16,
+            DetailLevel::Dots => 6,
+        }
+    }
+
+    fn detail_level(&self) -> DetailLevel {
+        self.canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.detail_level())
+    }
+
     pub fn bounding_rect(&self, samples: usize) -> Rect {
         // 收集曲线上的所有离散采样点
         let mut min_x = f32::MAX;
@@ -150,256 +381,9 @@ impl BezierWidget {
         Rect::from_min_max(min_pos, max_pos)
     }
 
-    /// 检测鼠标位置命中的元素
-    fn hit_test(&mut self, world_pos: Pos2) -> DragType {
-        let hit_radius: f32 = 10.0
-            * self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.transform.scaling);
-
-        let full_anchors = std::iter::once(&self.edge.source_anchor)
-            .chain(self.edge.control_anchors.iter())
-            .chain(std::iter::once(&self.edge.target_anchor))
-            .collect::<Vec<_>>();
-
-        // 检测控制柄 (HandleIn)
-        for (i, anchor) in full_anchors.iter().enumerate() {
-            // 跳过首锚点
-            if i == 0 {
-                continue;
-            }
-            let offset = world_pos - anchor.handle_in_canvas_pos;
-            if offset.length() < hit_radius {
-                self.dragging_anchor_index = Some(i);
-                return DragType::HandleIn;
-            }
-        }
-        // 检测控制柄 (HandleOut)
-        for (i, anchor) in full_anchors.iter().enumerate() {
-            // 跳过尾锚点
-            if i == full_anchors.len() - 1 {
-                continue;
-            }
-
-            let offset = world_pos - anchor.handle_out_canvas_pos;
-            if offset.length() < hit_radius {
-                self.dragging_anchor_index = Some(i);
-                return DragType::HandleOut;
-            }
-        }
-
-        // 检测锚点
-        for (i, anchor) in full_anchors.iter().enumerate() {
-            if i == 0 || i == full_anchors.len() - 1 {
-                continue;
-            }
-            let offset = world_pos - anchor.canvas_pos;
-            if offset.length() < hit_radius {
-                self.dragging_anchor_index = Some(i);
-                return DragType::Anchor;
-            }
-        }
-
-        // 贝塞尔曲线路径检测 (包围盒粗略检测 + 采样点精细检测)
-        let bounding_rect = self.bounding_rect(100); // 采样 100 个点用于包围盒计算
-        if bounding_rect.contains(world_pos) {
-            // 如果鼠标位置在包围盒内，进行更精确的采样点检测
-            let mut curve_points = Vec::new();
-            for i in 0..full_anchors.len() - 1 {
-                let start_anchor = &full_anchors[i];
-                let end_anchor = &full_anchors[i + 1];
-                for t in 0..=100 {
-                    let t = t as f32 / 100.0;
-                    let point = cubic_bezier(
-                        start_anchor.canvas_pos,
-                        start_anchor.handle_out_canvas_pos,
-                        end_anchor.handle_in_canvas_pos,
-                        end_anchor.canvas_pos,
-                        t,
-                    );
-                    curve_points.push(point);
-                }
-            }
-
-            for point in &curve_points {
-                if (world_pos - *point).length() < hit_radius {
-                    return DragType::MoveBezier; // 命中贝塞尔曲线路径
-                }
-            }
-        }
-
-        DragType::None // 没有命中任何元素
-    }
-
-    pub fn apply_actions(&mut self, response: &egui::Response, ui: &mut Ui) {
-        // println!("hover pos: {:?}", response.hover_pos());
-        let mouse_canvas_pos = self.canvas_state_resource.read_resource(|canvas_state| {
-            canvas_state.to_canvas(response.hover_pos().unwrap_or_default())
-        });
-
-        // 拖拽锚点
-        if self.dragging == DragType::Anchor {
-            if let Some(index) = self.dragging_anchor_index {
-                self.drag_anchor(index, ui);
-                if ui.input(|i| i.pointer.button_released(PointerButton::Primary)) {
-                    self.dragging = DragType::None;
-                    self.dragging_anchor_index = None;
-                }
-                ui.ctx().request_repaint(); // 持续重绘
-                return; // 提前返回，避免其他交互干扰
-            }
-        }
-        // 拖拽控制柄 (HandleIn)
-        if self.dragging == DragType::HandleIn {
-            if let Some(index) = self.dragging_anchor_index {
-                self.drag_handle_in(index, ui);
-                if ui.input(|i| i.pointer.button_released(PointerButton::Primary)) {
-                    self.dragging = DragType::None;
-                    self.dragging_anchor_index = None;
-                }
-                ui.ctx().request_repaint(); // 持续重绘
-                return; // 提前返回，避免其他交互干扰
-            }
-        }
-        // 拖拽控制柄 (HandleOut)
-        if self.dragging == DragType::HandleOut {
-            if let Some(index) = self.dragging_anchor_index {
-                self.drag_handle_out(index, ui);
-                if ui.input(|i| i.pointer.button_released(PointerButton::Primary)) {
-                    self.dragging = DragType::None;
-                    self.dragging_anchor_index = None;
-                }
-                ui.ctx().request_repaint(); // 持续重绘
-                return; // 提前返回，避免其他交互干扰
-            }
-        }
-        // 拖拽贝塞尔曲线
-        if self.dragging == DragType::MoveBezier {
-            self.drag_bezier(ui);
-            if ui.input(|i| i.pointer.button_released(PointerButton::Primary)) {
-                self.dragging = DragType::None;
-            }
-            ui.ctx().request_repaint(); // 持续重绘
-            return; // 提前返回，避免其他交互干扰
-        }
-
-        if response.hovered() {
-            // println!("response.hovered()");
-            let drag_type = self.hit_test(mouse_canvas_pos);
-            // println!("drag_type: {:?}", drag_type);
-            match drag_type {
-                DragType::Anchor
-                | DragType::HandleIn
-                | DragType::HandleOut
-                | DragType::MoveBezier => {
-                    // 根据不同的 DragType 设置不同的鼠标样式 (可选)
-                    ui.output_mut(|o| o.cursor_icon = CursorIcon::Grab);
-                }
-                DragType::None => {
-                    // 恢复默认鼠标样式 (可选)
-                    // ui.output_mut(|o| o.cursor_icon = CursorIcon::Default);
-                }
-            }
-
-            if response.double_clicked() {
-                ui.ctx().request_repaint();
-            }
-            if response.drag_started() && drag_type != DragType::None {
-                self.dragging = drag_type; // 开始拖拽
-                ui.ctx().request_repaint();
-            }
-        }
-    }
-
-    fn drag_bezier(&mut self, ui: &mut Ui) {
-        let delta = ui.input(|i| i.pointer.delta())
-            / self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.transform.scaling);
-        self.edge.source_anchor.canvas_pos += delta;
-        self.edge.target_anchor.canvas_pos += delta;
-        // 拖拽控制锚点
-        for anchor in &mut self.edge.control_anchors {
-            anchor.canvas_pos += delta;
-        }
-        // 拖拽控制柄
-        self.edge.source_anchor.handle_in_canvas_pos += delta;
-        self.edge.source_anchor.handle_out_canvas_pos += delta;
-        self.edge.target_anchor.handle_in_canvas_pos += delta;
-        self.edge.target_anchor.handle_out_canvas_pos += delta;
-        // 控制锚点的控制柄也需要拖拽
-        for anchor in &mut self.edge.control_anchors {
-            anchor.handle_in_canvas_pos += delta;
-            anchor.handle_out_canvas_pos += delta;
-        }
-    }
-
-    fn drag_anchor(&mut self, index: usize, ui: &mut Ui) {
-        let full_anchors = std::iter::once(&mut self.edge.source_anchor)
-            .chain(self.edge.control_anchors.iter_mut())
-            .chain(std::iter::once(&mut self.edge.target_anchor));
-        let mut all_anchors: Vec<&mut BezierAnchor> = full_anchors.collect();
-
-        let anchor = &mut all_anchors[index];
-
-        let delta = ui.input(|i| i.pointer.delta())
-            / self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.transform.scaling);
-        anchor.canvas_pos += delta;
-        anchor.handle_in_canvas_pos += delta;
-        anchor.handle_out_canvas_pos += delta;
-        // println!("anchor: {:?}", anchor.canvas_pos);
-        // 如果锚点是平滑状态，需要强制更新控制柄
-        if anchor.is_smooth {
-            anchor.enforce_smooth();
-        }
-    }
-
-    fn drag_handle_in(&mut self, index: usize, ui: &mut Ui) {
-        // println!("drag_handle_in: {:?}", index);
-        let full_anchors = std::iter::once(&mut self.edge.source_anchor)
-            .chain(self.edge.control_anchors.iter_mut())
-            .chain(std::iter::once(&mut self.edge.target_anchor));
-        let mut all_anchors: Vec<&mut BezierAnchor> = full_anchors.collect();
-        let anchor = &mut all_anchors[index];
-
-        let delta = ui.input(|i| i.pointer.delta())
-            / self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.transform.scaling);
-        anchor.handle_in_canvas_pos += delta;
-
-        if anchor.is_smooth {
-            let mirror_delta =
-                anchor.canvas_pos - (anchor.handle_in_canvas_pos - anchor.canvas_pos);
-            anchor.handle_out_canvas_pos = mirror_delta;
-        }
-    }
-
-    fn drag_handle_out(&mut self, index: usize, ui: &mut Ui) {
-        // println!("drag_handle_out: {:?}", index);
-        let full_anchors = std::iter::once(&mut self.edge.source_anchor)
-            .chain(self.edge.control_anchors.iter_mut())
-            .chain(std::iter::once(&mut self.edge.target_anchor));
-        let mut all_anchors: Vec<&mut BezierAnchor> = full_anchors.collect();
-        let anchor = &mut all_anchors[index];
-
-        let delta = ui.input(|i| i.pointer.delta())
-            / self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.transform.scaling);
-        anchor.handle_out_canvas_pos += delta;
-
-        if anchor.is_smooth {
-            let mirror_delta =
-                anchor.canvas_pos - (anchor.handle_out_canvas_pos - anchor.canvas_pos);
-            anchor.handle_in_canvas_pos = mirror_delta;
-        }
-    }
-
     fn draw_bezier(&self, ui: &mut Ui) {
         // println!("BezierWidget::draw_bezier");
+        let stroke = self.stroke(ui);
         let painter = ui.painter();
         // let canvas_state_resource: CanvasStateResource =
         //     ui.ctx().data(|d| d.get_temp(Id::NULL)).unwrap();
@@ -409,68 +393,88 @@ impl BezierWidget {
             .chain(std::iter::once(&self.edge.target_anchor))
             .collect::<Vec<_>>();
 
-        // 绘制所有锚点和控制柄
-        for (i, anchor) in full_anchors.iter().enumerate() {
-            let radius = 3.0
-                * self
+        // 缩小时不画锚点、控制柄和箭头
+        let full_detail = self.detail_level() == DetailLevel::Full;
+
+        if full_detail {
+            // 绘制所有锚点和控制柄
+            for (i, anchor) in full_anchors.iter().enumerate() {
+                let radius = 3.0
+                    * self
+                        .canvas_state_resource
+                        .read_resource(|canvas_state| canvas_state.transform.scaling);
+
+                // let color = if anchor.selected {
+                //     egui::Color32::GOLD
+                // } else {
+                //     egui::Color32::from_rgba_premultiplied(150, 150, 10, 200)
+                // };
+                let color = egui::Color32::GOLD;
+                let circle_screen_pos = self
                     .canvas_state_resource
-                    .read_resource(|canvas_state| canvas_state.transform.scaling);
-
-            // let color = if anchor.selected {
-            //     egui::Color32::GOLD
-            // } else {
-            //     egui::Color32::from_rgba_premultiplied(150, 150, 10, 200)
-            // };
-            let color = egui::Color32::GOLD;
-            let circle_screen_pos = self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.to_screen(anchor.canvas_pos));
-
-            if i != 0 && i != full_anchors.len() - 1 {
-                // 绘制锚点
-                painter.circle(circle_screen_pos, radius, color, (1.0, egui::Color32::GOLD));
-            }
-
-            let handle_in_screen_pos = self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.to_screen(anchor.handle_in_canvas_pos));
-            let handle_out_screen_pos = self
-                .canvas_state_resource
-                .read_resource(|canvas_state| canvas_state.to_screen(anchor.handle_out_canvas_pos));
-
-            // 绘制控制柄线
+                    .read_resource(|canvas_state| canvas_state.to_screen(anchor.canvas_pos));
+
+                if i != 0 && i != full_anchors.len() - 1 {
+                    // 绘制锚点，平滑锚点为圆形，尖锐锚点为方形
+                    if anchor.is_smooth {
+                        painter.circle(
+                            circle_screen_pos,
+                            radius,
+                            color,
+                            (1.0, egui::Color32::GOLD),
+                        );
+                    } else {
+                        painter.rect_filled(
+                            Rect::from_center_size(circle_screen_pos, Vec2::splat(radius * 2.0)),
+                            0.0,
+                            color,
+                        );
+                    }
+                }
 
-            // 首点没有入控制柄
-            if i != 0 {
-                painter.line_segment(
-                    [circle_screen_pos, handle_in_screen_pos],
-                    (3.0, egui::Color32::LIGHT_BLUE),
-                );
-            }
-            // 末点没有出控制柄
-            if i != full_anchors.len() - 1 {
-                painter.line_segment(
-                    [circle_screen_pos, handle_out_screen_pos],
-                    (3.0, egui::Color32::LIGHT_RED),
-                );
-            }
+                let handle_in_screen_pos =
+                    self.canvas_state_resource.read_resource(|canvas_state| {
+                        canvas_state.to_screen(anchor.handle_in_canvas_pos)
+                    });
+                let handle_out_screen_pos =
+                    self.canvas_state_resource.read_resource(|canvas_state| {
+                        canvas_state.to_screen(anchor.handle_out_canvas_pos)
+                    });
+
+                // 绘制控制柄线
+
+                // 首点没有入控制柄
+                if i != 0 {
+                    painter.line_segment(
+                        [circle_screen_pos, handle_in_screen_pos],
+                        (3.0, egui::Color32::LIGHT_BLUE),
+                    );
+                }
+                // 末点没有出控制柄
+                if i != full_anchors.len() - 1 {
+                    painter.line_segment(
+                        [circle_screen_pos, handle_out_screen_pos],
+                        (3.0, egui::Color32::LIGHT_RED),
+                    );
+                }
 
-            // 绘制控制柄点
-            if i != 0 {
-                painter.circle(
-                    handle_in_screen_pos,
-                    radius,
-                    egui::Color32::BLUE,
-                    (3.0, egui::Color32::LIGHT_BLUE),
-                );
-            }
-            if i != full_anchors.len() - 1 {
-                painter.circle(
-                    handle_out_screen_pos,
-                    radius,
-                    egui::Color32::RED,
-                    (3.0, egui::Color32::LIGHT_RED),
-                );
+                // 绘制控制柄点
+                if i != 0 {
+                    painter.circle(
+                        handle_in_screen_pos,
+                        radius,
+                        egui::Color32::BLUE,
+                        (3.0, egui::Color32::LIGHT_BLUE),
+                    );
+                }
+                if i != full_anchors.len() - 1 {
+                    painter.circle(
+                        handle_out_screen_pos,
+                        radius,
+                        egui::Color32::RED,
+                        (3.0, egui::Color32::LIGHT_RED),
+                    );
+                }
             }
         }
 
@@ -495,16 +499,19 @@ impl BezierWidget {
                 });
 
                 // 细分三次贝塞尔曲线为线段
-                for t in 0..=100 {
-                    let t = t as f32 / 100.0;
+                let segments = self.curve_segments();
+                for t in 0..=segments {
+                    let t = t as f32 / segments as f32;
                     let point = cubic_bezier(screen_start, screen_cp1, screen_cp2, screen_end, t);
                     path.push(point);
                 }
             }
-            painter.add(Shape::line(path, Stroke::new(2.0, egui::Color32::GRAY)));
+            painter.add(Shape::line(path, stroke));
         }
 
-        self.draw_arrow(painter);
+        if full_detail {
+            self.draw_arrow(painter, stroke);
+        }
         // self.draw_bounding_rect(painter);
     }
 
@@ -522,7 +529,7 @@ impl BezierWidget {
     //     );
     // }
 
-    fn draw_arrow(&self, painter: &egui::Painter) {
+    fn draw_arrow(&self, painter: &egui::Painter, stroke: Stroke) {
         let scale = self
             .canvas_state_resource
             .read_resource(|canvas_state| canvas_state.transform.scaling);
@@ -530,9 +537,6 @@ impl BezierWidget {
         // 箭头的原始长度（例如 10.0），再乘以缩放系数
         let arrow_length = 10.0 * scale;
 
-        // 同样，你也可以让线条粗细随缩放变化
-        let arrow_stroke_width = 2.0;
-
         // 3. 在整条曲线最后端画箭头
         //    由于 full_anchors 最后一项就是 target_anchor，让我们直接取它使用
         let target_anchor = &self.edge.target_anchor;
@@ -569,7 +573,8 @@ impl BezierWidget {
             let left_dir = rotate(dir_norm, angle) * arrow_length;
             let right_dir = rotate(dir_norm, -angle) * arrow_length;
 
-            let arrow_stroke = (arrow_stroke_width, egui::Color32::GRAY);
+            // 同样，你也可以让线条粗细随缩放变化
+            let arrow_stroke = stroke;
 
             // 在目标端画两条短线
             painter.line_segment([screen_end, screen_end - left_dir], arrow_stroke);
@@ -625,7 +630,7 @@ impl Widget for &mut BezierWidget {
         // );
         // let (pos, desired_size) = self.desired_size();
         // let rect = Rect::from_min_size(pos, desired_size);
-        let bounding_rect = self.bounding_rect(100);
+        let bounding_rect = self.bounding_rect(self.curve_segments());
         let screen_rect = self
             .canvas_state_resource
             .read_resource(|canvas_state| canvas_state.to_screen_rect(bounding_rect));
@@ -633,14 +638,15 @@ impl Widget for &mut BezierWidget {
         let response = ui.allocate_rect(screen_rect, Sense::click_and_drag());
 
         self.draw_bezier(ui);
-        self.draw_bounding_rect(ui);
+        if self.detail_level() == DetailLevel::Full {
+            self.draw_bounding_rect(ui);
+        }
         // ui.painter().rect(
         //     response.rect,
         //     0.0,
         //     egui::Color32::TRANSPARENT,
         //     Stroke::new(1.0, egui::Color32::ORANGE),
         // );
-        self.apply_actions(&response, ui);
         response
     }
 }
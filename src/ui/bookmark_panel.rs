@@ -0,0 +1,213 @@
+use egui::{RichText, ScrollArea, TextEdit};
+
+use crate::canvas::PresentationStep;
+use crate::resource::{CanvasStateResource, GraphResource};
+
+/// 视角书签侧边栏，保存当前视角并在书签之间跳转，也用来编辑演示路径
+#[derive(Debug)]
+pub struct BookmarkPanel {
+    pub graph_resource: GraphResource,
+    pub canvas_state_resource: CanvasStateResource,
+    /// 正在重命名的书签
+    renaming: Option<usize>,
+}
+
+impl BookmarkPanel {
+    pub fn new(graph_resource: GraphResource, canvas_state_resource: CanvasStateResource) -> Self {
+        Self {
+            graph_resource,
+            canvas_state_resource,
+            renaming: None,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("bookmark_panel")
+            .resizable(true)
+            .default_width(200.0)
+            .show(ctx, |ui| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    self.bookmarks_ui(ui);
+                    ui.add_space(12.0);
+                    self.presentation_ui(ui);
+                });
+            });
+    }
+
+    fn bookmarks_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Bookmarks");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .button("Add")
+                    .on_hover_text("Save the current view as a bookmark")
+                    .clicked()
+                {
+                    self.canvas_state_resource.with_resource(|canvas_state| {
+                        let name = format!("View {}", canvas_state.bookmarks.len() + 1);
+                        canvas_state.add_bookmark(name);
+                    });
+                }
+            });
+        });
+        ui.separator();
+
+        let mut bookmarks = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.bookmarks.clone());
+        if bookmarks.is_empty() {
+            ui.label(RichText::new("No bookmarks yet. Click Add to save the current view.").weak());
+            return;
+        }
+
+        let mut go_to = None;
+        let mut changed = false;
+        let mut remove = None;
+        let mut move_up = None;
+        let mut add_to_path = None;
+        for (index, bookmark) in bookmarks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                // 前九个书签可以用数字键跳转
+                let key = if index < 9 {
+                    (index + 1).to_string()
+                } else {
+                    String::new()
+                };
+                ui.add_sized([12.0, 18.0], egui::Label::new(RichText::new(key).weak()));
+
+                if self.renaming == Some(index) {
+                    let response =
+                        ui.add(TextEdit::singleline(&mut bookmark.name).desired_width(110.0));
+                    changed |= response.changed();
+                    if response.lost_focus() {
+                        self.renaming = None;
+                    } else {
+                        response.request_focus();
+                    }
+                } else {
+                    let response = ui
+                        .button(&bookmark.name)
+                        .on_hover_text("Click to go there, double-click to rename");
+                    if response.double_clicked() {
+                        self.renaming = Some(index);
+                    } else if response.clicked() {
+                        go_to = Some(index);
+                    }
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("Delete").clicked() {
+                        remove = Some(index);
+                    }
+                    if ui
+                        .add_enabled(index > 0, egui::Button::new("Up").small())
+                        .clicked()
+                    {
+                        move_up = Some(index);
+                    }
+                    if ui
+                        .small_button("Path")
+                        .on_hover_text("Add this view to the presentation path")
+                        .clicked()
+                    {
+                        add_to_path = Some(index);
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = move_up {
+            bookmarks.swap(index - 1, index);
+            self.renaming = None;
+            changed = true;
+        }
+        let path_step = add_to_path.map(|index| PresentationStep::View(bookmarks[index].clone()));
+        if let Some(index) = remove {
+            bookmarks.remove(index);
+            self.renaming = None;
+            changed = true;
+        }
+        self.canvas_state_resource.with_resource(|canvas_state| {
+            if changed {
+                canvas_state.bookmarks = bookmarks;
+                canvas_state.mark_changed();
+            }
+            if let Some(index) = go_to {
+                canvas_state.go_to_bookmark(index);
+            }
+            if let Some(step) = path_step {
+                canvas_state.presentation.push(step);
+                canvas_state.mark_changed();
+            }
+        });
+    }
+
+    fn presentation_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Presentation");
+        ui.separator();
+
+        let mut steps = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.presentation.clone());
+        if steps.is_empty() {
+            ui.label(
+                RichText::new(
+                    "The path is empty. Add nodes or views from the Present menu, \
+                     or click Path next to a bookmark.",
+                )
+                .weak(),
+            );
+            return;
+        }
+
+        let mut remove = None;
+        let mut move_up = None;
+        for (index, step) in steps.iter().enumerate() {
+            let label = match step {
+                PresentationStep::Node(node_id) => self.graph_resource.read_resource(|graph| {
+                    let text = graph
+                        .find_node_by_id(*node_id)
+                        .and_then(|node_index| graph.get_node(node_index))
+                        .map(|node| node.text.clone());
+                    match text {
+                        Some(text) if !text.is_empty() => format!("Node {node_id}: {text}"),
+                        Some(_) => format!("Node {node_id}"),
+                        None => format!("Node {node_id} (deleted)"),
+                    }
+                }),
+                PresentationStep::View(bookmark) => bookmark.name.clone(),
+            };
+            ui.horizontal(|ui| {
+                ui.add_sized(
+                    [12.0, 18.0],
+                    egui::Label::new(RichText::new((index + 1).to_string()).weak()),
+                );
+                ui.label(label);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    if ui
+                        .add_enabled(index > 0, egui::Button::new("Up").small())
+                        .clicked()
+                    {
+                        move_up = Some(index);
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = move_up {
+            steps.swap(index - 1, index);
+        }
+        if let Some(index) = remove {
+            steps.remove(index);
+        }
+        if move_up.is_some() || remove.is_some() {
+            self.canvas_state_resource.with_resource(|canvas_state| {
+                canvas_state.presentation = steps;
+                canvas_state.mark_changed();
+            });
+        }
+    }
+}
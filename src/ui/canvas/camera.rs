@@ -0,0 +1,98 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use petgraph::graph::NodeIndex;
+
+use crate::layout::nodes_bounds;
+
+use super::data::CanvasWidget;
+
+/// 缩放到显示整个图
+pub const ZOOM_TO_FIT_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::NONE, Key::Home);
+
+/// 缩放到显示选中的元素
+pub const ZOOM_TO_SELECTION_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::NONE, Key::F);
+
+impl CanvasWidget {
+    /// 平滑缩放到完整显示所有可见的节点
+    pub fn zoom_to_fit(&self) {
+        let bounds = self.graph_resource.read_resource(|graph| {
+            let hidden = graph.hidden_nodes();
+            let nodes: Vec<NodeIndex> = graph
+                .graph
+                .node_indices()
+                .filter(|node_index| !hidden.contains(node_index))
+                .collect();
+            nodes_bounds(graph, &nodes)
+        });
+        if let Some(bounds) = bounds {
+            self.canvas_state_resource
+                .with_resource(|canvas_state| canvas_state.zoom_to_rect(bounds));
+        }
+    }
+
+    /// 平滑缩放到完整显示选中的节点，选中边时显示边的两端
+    pub fn zoom_to_selection(&self) {
+        let bounds = self.graph_resource.read_resource(|graph| {
+            let mut nodes = graph.get_selected_nodes();
+            for edge_index in graph.get_selected_edges() {
+                if let Some((source, target)) = graph.graph.edge_endpoints(edge_index) {
+                    nodes.extend([source, target]);
+                }
+            }
+            nodes_bounds(graph, &nodes)
+        });
+        if let Some(bounds) = bounds {
+            self.canvas_state_resource
+                .with_resource(|canvas_state| canvas_state.zoom_to_rect(bounds));
+        }
+    }
+
+    /// 平滑移动到编号为 `node_id` 的节点并选中它，找不到节点时返回 false
+    pub fn fly_to_node(&self, node_id: u64) -> bool {
+        let bounds = self.graph_resource.with_resource(|graph| {
+            let node_index = graph.find_node_by_id(node_id)?;
+            graph.selected.clear();
+            graph.select_node(node_index);
+            nodes_bounds(graph, &[node_index])
+        });
+        let Some(bounds) = bounds else {
+            return false;
+        };
+        // 缩得太小时放大到原始比例，保证能看清节点
+        self.canvas_state_resource
+            .with_resource(|canvas_state| canvas_state.fly_to(bounds.center(), 1.0));
+        true
+    }
+
+    pub fn handle_camera_shortcuts(&self, ui: &mut egui::Ui) {
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        if ui.input_mut(|i| i.consume_shortcut(&ZOOM_TO_FIT_SHORTCUT)) {
+            self.zoom_to_fit();
+        }
+        if ui.input_mut(|i| i.consume_shortcut(&ZOOM_TO_SELECTION_SHORTCUT)) {
+            self.zoom_to_selection();
+        }
+
+        // 数字键 1-9 跳转到对应的书签
+        const BOOKMARK_KEYS: [Key; 9] = [
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        for (index, key) in BOOKMARK_KEYS.into_iter().enumerate() {
+            if ui.input_mut(|i| i.consume_key(Modifiers::NONE, key)) {
+                self.canvas_state_resource
+                    .with_resource(|canvas_state| canvas_state.go_to_bookmark(index));
+            }
+        }
+    }
+}
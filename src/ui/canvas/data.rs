@@ -1,4 +1,4 @@
-use egui::Pos2;
+use egui::{Pos2, Rect};
 use petgraph::graph::NodeIndex;
 
 use crate::{
@@ -18,6 +18,10 @@ pub struct CanvasWidget {
     // pub input_state: InputState,
     pub input_busy: bool,
     pub drag_select_range: Option<[Pos2; 2]>,
+    /// 在小地图上拖动时固定的显示范围（画布坐标）
+    pub minimap_bounds: Option<Rect>,
+    /// 演示模式中当前的步骤，不在演示时为空
+    pub presentation_step: Option<usize>,
 }
 
 impl CanvasWidget {
@@ -38,6 +42,8 @@ impl CanvasWidget {
             // particle_system_resource,
             input_busy: false,
             drag_select_range: None,
+            minimap_bounds: None,
+            presentation_step: None,
         }
     }
 
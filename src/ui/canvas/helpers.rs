@@ -18,27 +18,40 @@ use crate::{
 
 use super::data::CanvasWidget;
 
-pub fn draw_grid(ui: &mut egui::Ui, canvas_state: &CanvasState, screen_rect: egui::Rect) {
-    // println!("draw_grid");
-    let painter = ui.painter_at(screen_rect);
-
-    // 基准网格间距（画布坐标系中的单位）
-    let base_grid_size = 100.0;
+/// 基准网格间距（画布坐标系中的单位）
+const BASE_GRID_SIZE: f32 = 100.0;
 
+/// 当前缩放下相邻两级网格的间距，以及用于淡入淡出的连续级别
+fn grid_levels(scaling: f32) -> (f32, f32, f32) {
     // 计算当前缩放下的网格像素大小
-    let grid_pixels = base_grid_size * canvas_state.transform.scaling;
+    let grid_pixels = BASE_GRID_SIZE * scaling;
 
     // 计算网格级别
-    let level_f = -(grid_pixels / base_grid_size).log2();
-    // let level_f_offset = level_f + 0.5;
+    let level_f = -(grid_pixels / BASE_GRID_SIZE).log2();
     let level = level_f.floor() as i32;
-    // println!("level_f: {:?}", level_f);
-    // println!("level: {:?}", level);
-    // let level = level_f.floor() as i32;
 
     // 计算两个相邻级别的网格大小
-    let grid_size_1 = base_grid_size * 2.0_f32.powi(level);
-    let grid_size_2 = base_grid_size * 2.0_f32.powi(level + 1);
+    let grid_size_1 = BASE_GRID_SIZE * 2.0_f32.powi(level);
+    let grid_size_2 = BASE_GRID_SIZE * 2.0_f32.powi(level + 1);
+    (grid_size_1, grid_size_2, level_f)
+}
+
+/// 当前最明显的一级网格的间距，用于吸附
+pub fn visible_grid_size(scaling: f32) -> f32 {
+    let (grid_size_1, grid_size_2, level_f) = grid_levels(scaling);
+    // 与 draw_grid 的透明度一致：小数部分超过一半时第二级更明显
+    if level_f - level_f.floor() < 0.5 {
+        grid_size_1
+    } else {
+        grid_size_2
+    }
+}
+
+pub fn draw_grid(ui: &mut egui::Ui, canvas_state: &CanvasState, screen_rect: egui::Rect) {
+    // println!("draw_grid");
+    let painter = ui.painter_at(screen_rect);
+
+    let (grid_size_1, grid_size_2, level_f) = grid_levels(canvas_state.transform.scaling);
 
     // 计算两个级别的透明度
     let t = level_f.fract();
@@ -199,25 +212,12 @@ pub fn draw_grid(ui: &mut egui::Ui, canvas_state: &CanvasState, screen_rect: egu
 }
 
 impl CanvasWidget {
-    pub fn hit_test_node(&self, ui: &mut egui::Ui, screen_pos: egui::Pos2) -> Option<NodeIndex> {
-        self.graph_resource.read_resource(|graph| {
-            graph.graph.node_indices().find(|&node_index| {
-                let node_render_info: Option<NodeRenderInfo> = ui
-                    .ctx()
-                    .data(|d| d.get_temp(Id::new(node_index.index().to_string())));
-                // println!("node_render_info: {:?}", node_render_info);
-                if let Some(node_render_info) = node_render_info {
-                    let node_screen_rect =
-                        self.canvas_state_resource.read_resource(|canvas_state| {
-                            canvas_state.to_screen_rect(node_render_info.canvas_rect)
-                        });
-                    if node_screen_rect.contains(screen_pos) {
-                        return true;
-                    }
-                }
-                false
-            })
-        })
+    pub fn hit_test_node(&self, _ui: &mut egui::Ui, screen_pos: egui::Pos2) -> Option<NodeIndex> {
+        let canvas_pos = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.to_canvas(screen_pos));
+        self.graph_resource
+            .read_resource(|graph| graph.nodes_at(canvas_pos).first().copied())
     }
 
     pub fn make_temp_edge(&self, ui: &mut egui::Ui, node_index: NodeIndex) -> Option<TempEdge> {
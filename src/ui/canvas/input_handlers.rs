@@ -1,4 +1,4 @@
-use egui::{emath::TSTransform, Color32, Id, Pos2, Rect, Response, Stroke};
+use egui::{emath::TSTransform, Color32, Id, Rect, Response, Stroke};
 
 use crate::{canvas::CanvasState, ui::helpers::draw_dashed_rect_with_offset};
 
@@ -145,24 +145,4 @@ impl CanvasWidget {
             });
         }
     }
-
-    pub fn handle_tab(&mut self, ui: &mut egui::Ui) {
-        if self.tab_pressed(ui) {
-            let selected_node_indices = self
-                .graph_resource
-                .read_resource(|graph| graph.get_selected_nodes());
-
-            let src_node_canvas_pos_vec = selected_node_indices
-                .iter()
-                .map(|index| {
-                    self.canvas_state_resource.read_resource(|canvas_state| {
-                        canvas_state.to_canvas(
-                            self.graph_resource
-                                .read_resource(|graph| graph.get_node(*index).unwrap().position),
-                        )
-                    })
-                })
-                .collect::<Vec<Pos2>>();
-        }
-    }
 }
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use egui::{Id, Order, Pos2, Rect, Sense, Stroke, Vec2};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::colors::{
+    edge_stroke, minimap_background, minimap_viewport, node_background, node_border_selected,
+};
+use crate::layout::node_rects;
+
+use super::data::CanvasWidget;
+
+/// 小地图的最大尺寸
+const MINIMAP_SIZE: Vec2 = Vec2::new(200.0, 150.0);
+
+/// 小地图与画布边缘的距离
+const MINIMAP_MARGIN: f32 = 12.0;
+
+/// 小地图内容与边框的距离
+const MINIMAP_PADDING: f32 = 6.0;
+
+impl CanvasWidget {
+    /// 在画布右下角绘制所有节点和边的缩略图以及当前视口，点击或拖动时把视口中心移到该处
+    pub fn draw_minimap(&mut self, ui: &mut egui::Ui, screen_rect: Rect) {
+        let (show_minimap, viewport) = self.canvas_state_resource.read_resource(|canvas_state| {
+            (
+                canvas_state.show_minimap,
+                canvas_state.to_canvas_rect(screen_rect),
+            )
+        });
+        if !show_minimap || self.is_presenting() {
+            return;
+        }
+
+        let (rects, edges, selected) = self.graph_resource.read_resource(|graph| {
+            let nodes: Vec<NodeIndex> = graph
+                .graph
+                .node_indices()
+                .filter(|node_index| !graph.is_node_hidden(*node_index))
+                .collect();
+            let rects: HashMap<NodeIndex, Rect> = node_rects(graph, &nodes).into_iter().collect();
+            let edges: Vec<(NodeIndex, NodeIndex)> = graph
+                .graph
+                .edge_references()
+                .filter(|edge| {
+                    !graph.is_node_hidden(edge.source()) && !graph.is_node_hidden(edge.target())
+                })
+                .map(|edge| (edge.source(), edge.target()))
+                .collect();
+            (rects, edges, graph.get_selected_nodes())
+        });
+        if rects.is_empty() {
+            return;
+        }
+
+        // 拖动时固定显示范围，否则视口移出节点范围时比例会跟着变化
+        let bounds = self.minimap_bounds.unwrap_or_else(|| {
+            rects
+                .values()
+                .fold(viewport, |bounds, rect| bounds.union(*rect))
+        });
+        let padding = Vec2::splat(MINIMAP_PADDING);
+        let scale = ((MINIMAP_SIZE - padding * 2.0) / bounds.size()).min_elem();
+        let size = bounds.size() * scale + padding * 2.0;
+        let minimap_rect = Rect::from_min_size(
+            screen_rect.right_bottom() - size - Vec2::splat(MINIMAP_MARGIN),
+            size,
+        );
+        let to_minimap = |pos: Pos2| minimap_rect.min + padding + (pos - bounds.min) * scale;
+        let to_canvas = |pos: Pos2| bounds.min + (pos - minimap_rect.min - padding) / scale;
+
+        let response = egui::Area::new(Id::new("minimap"))
+            .order(Order::Middle)
+            .fixed_pos(minimap_rect.min)
+            .show(ui.ctx(), |ui| {
+                let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+                let theme = ui.ctx().theme();
+                let painter = ui.painter_at(rect);
+                painter.rect(
+                    rect,
+                    4.0,
+                    minimap_background(theme),
+                    Stroke::new(1.0, edge_stroke(theme)),
+                    egui::StrokeKind::Inside,
+                );
+
+                for (source, target) in &edges {
+                    if let (Some(source), Some(target)) = (rects.get(source), rects.get(target)) {
+                        painter.line_segment(
+                            [to_minimap(source.center()), to_minimap(target.center())],
+                            Stroke::new(0.5, edge_stroke(theme)),
+                        );
+                    }
+                }
+                for (node_index, node_rect) in &rects {
+                    let mini_rect =
+                        Rect::from_min_max(to_minimap(node_rect.min), to_minimap(node_rect.max));
+                    // 缩得太小时保证节点依然可见
+                    let mini_rect = Rect::from_center_size(
+                        mini_rect.center(),
+                        mini_rect.size().max(Vec2::splat(2.0)),
+                    );
+                    let color = if selected.contains(node_index) {
+                        node_border_selected(theme)
+                    } else {
+                        node_background(theme)
+                    };
+                    painter.rect_filled(mini_rect, 1.0, color);
+                }
+
+                let viewport_rect =
+                    Rect::from_min_max(to_minimap(viewport.min), to_minimap(viewport.max));
+                painter.rect_stroke(
+                    viewport_rect,
+                    0.0,
+                    Stroke::new(1.5, minimap_viewport(theme)),
+                    egui::StrokeKind::Middle,
+                );
+                response
+            })
+            .inner;
+
+        if response.is_pointer_button_down_on() {
+            self.minimap_bounds = Some(bounds);
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let target = to_canvas(pointer);
+                self.canvas_state_resource.with_resource(|canvas_state| {
+                    let scaling = canvas_state.transform.scaling;
+                    canvas_state.transform.translation =
+                        screen_rect.center().to_vec2() - target.to_vec2() * scaling;
+                });
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+            }
+        } else {
+            self.minimap_bounds = None;
+            if response.hovered() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+            }
+        }
+    }
+}
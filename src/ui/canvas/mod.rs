@@ -1,6 +1,9 @@
+pub mod camera;
 pub mod data;
 pub mod helpers;
 pub mod input;
 pub mod input_detector;
 pub mod input_handlers;
+pub mod minimap;
+pub mod presentation;
 pub mod widget_impl;
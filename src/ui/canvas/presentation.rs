@@ -0,0 +1,146 @@
+use egui::{Align2, Color32, FontId, Key, KeyboardShortcut, Modifiers, Rect, ViewportCommand};
+
+use crate::canvas::PresentationStep;
+use crate::layout::nodes_bounds;
+
+use super::data::CanvasWidget;
+
+/// 开始演示
+pub const PRESENTATION_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F5);
+
+/// 聚焦节点时四周留出的画布空间
+const FOCUS_MARGIN: f32 = 40.0;
+
+/// 聚焦区域外变暗的程度
+const DIM_ALPHA: u8 = 150;
+
+impl CanvasWidget {
+    pub fn is_presenting(&self) -> bool {
+        self.presentation_step.is_some()
+    }
+
+    /// 全屏并从演示路径的第一步开始，路径为空时什么都不做
+    pub fn start_presentation(&mut self, ctx: &egui::Context) {
+        self.presentation_step = None;
+        if self.step_presentation(true) {
+            ctx.send_viewport_cmd(ViewportCommand::Fullscreen(true));
+        }
+    }
+
+    pub fn stop_presentation(&mut self, ctx: &egui::Context) {
+        if self.presentation_step.take().is_some() {
+            ctx.send_viewport_cmd(ViewportCommand::Fullscreen(false));
+        }
+    }
+
+    /// 前进或后退到下一个有效的步骤，跳过已经删除或被折叠的节点
+    ///
+    /// 已经在路径的一端时停在原处，返回是否移动了。
+    fn step_presentation(&mut self, forward: bool) -> bool {
+        let steps = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.presentation.clone());
+        let mut index = self.presentation_step;
+        loop {
+            index = match (index, forward) {
+                (None, true) => Some(0),
+                (Some(i), true) if i + 1 < steps.len() => Some(i + 1),
+                (Some(i), false) if i > 0 => Some(i - 1),
+                _ => return false,
+            };
+            let Some(step) = index.and_then(|i| steps.get(i)) else {
+                return false;
+            };
+            if let Some(focus) = self.step_focus(step) {
+                self.presentation_step = index;
+                self.canvas_state_resource
+                    .with_resource(|canvas_state| match step {
+                        PresentationStep::Node(_) => canvas_state.zoom_to_rect(focus),
+                        PresentationStep::View(bookmark) => {
+                            canvas_state.animate_to(bookmark.transform)
+                        }
+                    });
+                return true;
+            }
+        }
+    }
+
+    /// 步骤聚焦的画布区域，视角步骤聚焦整个视口；节点不可见时为空
+    fn step_focus(&self, step: &PresentationStep) -> Option<Rect> {
+        match step {
+            PresentationStep::Node(node_id) => self.graph_resource.read_resource(|graph| {
+                let node_index = graph.find_node_by_id(*node_id)?;
+                if graph.hidden_nodes().contains(&node_index) {
+                    return None;
+                }
+                nodes_bounds(graph, &[node_index]).map(|rect| rect.expand(FOCUS_MARGIN))
+            }),
+            PresentationStep::View(_) => Some(Rect::EVERYTHING),
+        }
+    }
+
+    pub fn handle_presentation_keys(&mut self, ui: &mut egui::Ui) {
+        let ctx = ui.ctx().clone();
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        if !self.is_presenting() {
+            if ui.input_mut(|i| i.consume_shortcut(&PRESENTATION_SHORTCUT)) {
+                self.start_presentation(&ctx);
+            }
+            return;
+        }
+
+        let pressed = |keys: &[Key]| ui.input(|i| keys.iter().any(|key| i.key_pressed(*key)));
+        if pressed(&[Key::ArrowRight, Key::ArrowDown, Key::PageDown]) {
+            self.step_presentation(true);
+        }
+        if pressed(&[Key::ArrowLeft, Key::ArrowUp, Key::PageUp]) {
+            self.step_presentation(false);
+        }
+        if pressed(&[Key::Escape]) {
+            self.stop_presentation(&ctx);
+        }
+    }
+
+    /// 演示时把当前节点以外的区域调暗，并在右下角显示进度
+    pub fn draw_presentation_overlay(&self, ui: &mut egui::Ui, screen_rect: Rect) {
+        let Some(index) = self.presentation_step else {
+            return;
+        };
+        let (steps, current) = self.canvas_state_resource.read_resource(|canvas_state| {
+            let steps = canvas_state.presentation.len();
+            (steps, canvas_state.presentation.get(index).cloned())
+        });
+        let painter = ui.painter_at(screen_rect);
+
+        if let Some(step @ PresentationStep::Node(_)) = current {
+            if let Some(focus) = self.step_focus(&step) {
+                let focus = self
+                    .canvas_state_resource
+                    .read_resource(|canvas_state| canvas_state.to_screen_rect(focus))
+                    .intersect(screen_rect);
+                let dim = Color32::from_black_alpha(DIM_ALPHA);
+                let (outer, inner) = (screen_rect, focus);
+                for rect in [
+                    Rect::from_x_y_ranges(outer.x_range(), outer.min.y..=inner.min.y),
+                    Rect::from_x_y_ranges(outer.x_range(), inner.max.y..=outer.max.y),
+                    Rect::from_x_y_ranges(outer.min.x..=inner.min.x, inner.y_range()),
+                    Rect::from_x_y_ranges(inner.max.x..=outer.max.x, inner.y_range()),
+                ] {
+                    if rect.is_positive() {
+                        painter.rect_filled(rect, 0.0, dim);
+                    }
+                }
+            }
+        }
+
+        painter.text(
+            screen_rect.right_bottom() - egui::vec2(16.0, 12.0),
+            Align2::RIGHT_BOTTOM,
+            format!("{} / {}", index + 1, steps),
+            FontId::proportional(14.0),
+            ui.visuals().weak_text_color(),
+        );
+    }
+}
@@ -17,7 +17,16 @@ impl Widget for &mut CanvasWidget {
             draw_grid(ui, canvas_state, screen_rect);
         });
 
+        self.canvas_state_resource
+            .with_resource(|canvas_state| canvas_state.viewport = screen_rect);
+
+        // 更新上一帧修改过的节点和边，命中测试和视口裁剪都依赖空间索引
+        self.graph_resource
+            .with_resource(|graph| graph.update_spatial_index());
+
         self.input_manager.update(ui, &canvas_response);
+        self.handle_camera_shortcuts(ui);
+        self.handle_presentation_keys(ui);
 
         if let Some(edge) = self.temp_edge.as_ref() {
             // println!("temp_edge target: {:?}", edge.target);
@@ -53,6 +62,8 @@ impl Widget for &mut CanvasWidget {
         // self.update_selected_nodes();
         // self.draw_particle_system(ui, screen_rect);
         self.input_manager.draw_particle_system(ui, screen_rect);
+        self.draw_presentation_overlay(ui, screen_rect);
+        self.draw_minimap(ui, screen_rect);
         // self.post_render_actions(ui, &canvas_response);
 
         canvas_response
@@ -2,12 +2,18 @@ use egui::*;
 use petgraph::graph::EdgeIndex;
 
 use crate::{
-    geometry::{edge_offset_direction, intersect_rect_with_pos, IntersectDirection},
+    canvas::DetailLevel,
+    colors::{edge_label_background, edge_label_text},
+    geometry::{
+        edge_offset_direction, intersect_rect_with_pos,
+        orthogonal::{orthogonal_search_rect, route_orthogonal},
+        IntersectDirection,
+    },
     graph::{
         anchor::{BezierAnchor, LineAnchor},
-        edge::EdgeType,
-        helpers::{get_node_render_info, node_rect_center},
-        render_info::NodeRenderInfo,
+        edge::{EdgeRouting, EdgeType},
+        helpers::{edge_label_id, get_node_render_info, node_rect_center},
+        render_info::{EdgeLabelRenderInfo, NodeRenderInfo},
     },
     resource::{CanvasStateResource, GraphResource},
 };
@@ -15,6 +21,7 @@ use crate::{
 use super::{
     bezier::{BezierEdge, BezierWidget},
     line_edge::{LineEdge, LineWidget},
+    orthogonal_edge::{OrthogonalEdge, OrthogonalWidget},
 };
 
 pub struct EdgeWidget {
@@ -100,21 +107,34 @@ impl EdgeWidget {
             IntersectDirection::Bottom => Vec2::new(0.0, offset_amount),
         };
 
-        let source_anchor = BezierAnchor::new_smooth(source_canvas_pos).with_handles(
-            source_canvas_pos + handle_offset_source,
-            source_canvas_pos + handle_offset_source,
-        );
-        let target_anchor = BezierAnchor::new_smooth(target_canvas_pos).with_handles(
-            target_canvas_pos + handle_offset_target,
-            target_canvas_pos + handle_offset_target,
-        );
-
         // 获取已有贝塞尔曲线控制点锚点
-        let bezier_edge = self
-            .graph_resource
-            .read_resource(|graph| graph.get_edge(self.edge_index).unwrap().bezier_edge.clone());
+        let (bezier_edge, routing) = self.graph_resource.read_resource(|graph| {
+            let edge = graph.get_edge(self.edge_index).unwrap();
+            (edge.bezier_edge.clone(), edge.routing)
+        });
         let control_anchors = bezier_edge.control_anchors;
 
+        let (source_anchor, target_anchor) = match routing {
+            EdgeRouting::Automatic => (
+                BezierAnchor::new_smooth(source_canvas_pos).with_handles(
+                    source_canvas_pos + handle_offset_source,
+                    source_canvas_pos + handle_offset_source,
+                ),
+                BezierAnchor::new_smooth(target_canvas_pos).with_handles(
+                    target_canvas_pos + handle_offset_target,
+                    target_canvas_pos + handle_offset_target,
+                ),
+            ),
+            // 固定路由：端点跟随节点，控制柄保持用户调整过的偏移
+            EdgeRouting::Pinned => {
+                let mut source_anchor = bezier_edge.source_anchor;
+                let mut target_anchor = bezier_edge.target_anchor;
+                source_anchor.move_to(source_canvas_pos);
+                target_anchor.move_to(target_canvas_pos);
+                (source_anchor, target_anchor)
+            }
+        };
+
         let new_bezier_edge =
             BezierEdge::new(source_anchor, target_anchor).with_control_anchors(control_anchors);
 
@@ -187,37 +207,255 @@ impl EdgeWidget {
             graph.update_line_edge(self.edge_index, new_line_edge);
         });
     }
+
+    // 根据节点位置重新计算直角折线，绕开其他节点
+    fn update_orthogonal_edge(&self, ui: &egui::Ui) {
+        let (src_node_index, dst_node_index, edge_count) =
+            self.graph_resource.read_resource(|graph| {
+                let (src, dst) = graph.graph.edge_endpoints(self.edge_index).unwrap();
+                (src, dst, graph.edge_count_undirected(src, dst))
+            });
+
+        let (Some(src_node_render_info), Some(dst_node_render_info)) = (
+            get_node_render_info(src_node_index, ui),
+            get_node_render_info(dst_node_index, ui),
+        ) else {
+            return;
+        };
+
+        let src_node_canvas_center = src_node_render_info.canvas_center();
+        let dst_node_canvas_center = dst_node_render_info.canvas_center();
+        let mut src_center = src_node_canvas_center;
+        let mut dst_center = dst_node_canvas_center;
+
+        // 与贝塞尔边和直线相同，同一对节点之间有多条边时错开，避免重叠
+        if edge_count != 1 {
+            let offset_dir = edge_offset_direction(src_node_canvas_center, dst_node_canvas_center);
+            let offset_amount = 10.0;
+            src_center += offset_dir * offset_amount;
+            dst_center += offset_dir * offset_amount;
+        }
+
+        let Some((source_canvas_pos, source_dir)) =
+            intersect_rect_with_pos(src_node_render_info.canvas_rect, src_center, dst_center)
+        else {
+            return;
+        };
+        let Some((target_canvas_pos, target_dir)) =
+            intersect_rect_with_pos(dst_node_render_info.canvas_rect, dst_center, src_center)
+        else {
+            return;
+        };
+
+        // 只有路由搜索范围内的节点才可能挡路，首尾节点也作为障碍物，避免折线穿回节点内部
+        let corridor = orthogonal_search_rect(source_canvas_pos, target_canvas_pos);
+        let obstacles = self.graph_resource.read_resource(|graph| {
+            graph
+                .nodes_in_rect(corridor)
+                .into_iter()
+                .filter_map(|node_index| graph.node_rect(node_index))
+                .collect::<Vec<_>>()
+        });
+
+        let points = route_orthogonal(
+            source_canvas_pos,
+            source_dir,
+            target_canvas_pos,
+            target_dir,
+            &obstacles,
+        );
+
+        self.graph_resource.with_resource(|graph| {
+            graph.update_orthogonal_edge(self.edge_index, OrthogonalEdge::new(points));
+        });
+    }
+}
+
+impl EdgeWidget {
+    // 在边的弧长中点绘制标签，正在编辑时显示输入框
+    fn draw_label(&self, ui: &mut egui::Ui, edge_type: EdgeType) {
+        let label_id = edge_label_id(self.edge_index);
+        let (text, anchor, editing, rotate) = self.graph_resource.read_resource(|graph| {
+            let edge = graph.get_edge(self.edge_index).unwrap();
+            (
+                edge.text.clone(),
+                edge.label_anchor(&edge_type),
+                graph.get_editing_edge() == Some(self.edge_index),
+                graph.rotate_edge_labels,
+            )
+        });
+
+        // 缩小时不画标签，正在编辑的除外
+        let full_detail = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.detail_level())
+            == DetailLevel::Full;
+        let Some((canvas_pos, direction)) =
+            anchor.filter(|_| (text.is_some() && full_detail) || editing)
+        else {
+            ui.ctx()
+                .data_mut(|d| d.remove::<EdgeLabelRenderInfo>(label_id));
+            return;
+        };
+
+        let (screen_pos, scaling) = self.canvas_state_resource.read_resource(|canvas_state| {
+            (
+                canvas_state.to_screen(canvas_pos),
+                canvas_state.transform.scaling,
+            )
+        });
+        let theme = ui.ctx().theme();
+        let font = FontId::proportional(14.0 * scaling);
+        let padding = vec2(6.0, 2.0) * scaling;
+
+        let screen_rect = if editing {
+            // 编辑时不旋转，方便输入
+            let mut text = text.unwrap_or_default();
+            let text_width = ui
+                .painter()
+                .layout_no_wrap(text.clone(), font.clone(), edge_label_text(theme))
+                .size()
+                .x;
+            let row_height = ui.fonts(|fonts| fonts.row_height(&font));
+            let size = vec2(text_width.max(40.0 * scaling), row_height) + padding * 2.0;
+            let rect = Rect::from_center_size(screen_pos, size);
+
+            let edit_response = ui.put(
+                rect,
+                TextEdit::singleline(&mut text)
+                    .font(font)
+                    .text_color(edge_label_text(theme))
+                    .background_color(edge_label_background(theme))
+                    .horizontal_align(Align::Center),
+            );
+            let lost_focus = edit_response.lost_focus();
+            if !lost_focus {
+                edit_response.request_focus();
+            }
+            // 只在文本变化时修改边
+            let changed = edit_response.changed();
+            if changed || lost_focus {
+                self.graph_resource.with_resource(|graph| {
+                    if changed {
+                        graph.set_edge_text(self.edge_index, text);
+                    }
+                    if lost_focus {
+                        graph.set_editing_edge(None);
+                    }
+                });
+            }
+            rect
+        } else {
+            let galley =
+                ui.painter()
+                    .layout_no_wrap(text.unwrap_or_default(), font, edge_label_text(theme));
+            let size = galley.size() + padding * 2.0;
+
+            // 旋转时保持文字朝上
+            let mut angle = if rotate { direction.angle() } else { 0.0 };
+            if angle > std::f32::consts::FRAC_PI_2 {
+                angle -= std::f32::consts::PI;
+            } else if angle < -std::f32::consts::FRAC_PI_2 {
+                angle += std::f32::consts::PI;
+            }
+            let rot = emath::Rot2::from_angle(angle);
+
+            let painter = ui.painter();
+            let background = edge_label_background(theme);
+            if angle == 0.0 {
+                painter.rect_filled(
+                    Rect::from_center_size(screen_pos, size),
+                    CornerRadius::same((size.y / 2.0).min(255.0) as u8),
+                    background,
+                );
+            } else {
+                // 旋转后的胶囊形背景：一条粗线加两端的半圆
+                let radius = size.y / 2.0;
+                let half_length = rot * vec2((size.x / 2.0 - radius).max(0.0), 0.0);
+                let (start, end) = (screen_pos - half_length, screen_pos + half_length);
+                painter.line_segment([start, end], Stroke::new(size.y, background));
+                painter.circle_filled(start, radius, background);
+                painter.circle_filled(end, radius, background);
+            }
+
+            let text_pos = screen_pos - rot * (galley.size() / 2.0);
+            painter.add(
+                epaint::TextShape::new(text_pos, galley, edge_label_text(theme)).with_angle(angle),
+            );
+
+            let half = size / 2.0;
+            Rect::from_points(&[
+                screen_pos + rot * vec2(-half.x, -half.y),
+                screen_pos + rot * vec2(half.x, -half.y),
+                screen_pos + rot * vec2(half.x, half.y),
+                screen_pos + rot * vec2(-half.x, half.y),
+            ])
+        };
+
+        let canvas_rect = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.to_canvas_rect(screen_rect));
+        ui.ctx()
+            .data_mut(|d| d.insert_temp(label_id, EdgeLabelRenderInfo { canvas_rect }));
+    }
 }
 
 impl Widget for EdgeWidget {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         self.update_bezier_edge(ui);
         self.update_line_edge(ui);
+        // 折线路由开销较大，只为折线边计算
+        if self
+            .graph_resource
+            .read_resource(|graph| graph.edge_type_of(self.edge_index))
+            == Some(EdgeType::Orthogonal)
+        {
+            self.update_orthogonal_edge(ui);
+        }
         // println!("TempEdgeWidget::ui");
 
-        let edge_type = self
-            .graph_resource
-            .read_resource(|graph| graph.edge_type.clone());
+        let (edge_type, selected) = self.graph_resource.read_resource(|graph| {
+            (
+                graph
+                    .edge_type_of(self.edge_index)
+                    .unwrap_or(graph.edge_type),
+                graph.is_edge_selected(self.edge_index),
+            )
+        });
         let response = match edge_type {
             EdgeType::Bezier => {
                 let bezier_edge = self.graph_resource.read_resource(|graph| {
                     graph.get_edge(self.edge_index).unwrap().bezier_edge.clone()
                 });
-                ui.add(&mut BezierWidget::new(
-                    bezier_edge.clone(),
-                    self.canvas_state_resource,
-                ))
+                ui.add(
+                    &mut BezierWidget::new(bezier_edge.clone(), self.canvas_state_resource.clone())
+                        .selected(selected),
+                )
             }
             EdgeType::Line => {
                 let line_edge = self.graph_resource.read_resource(|graph| {
                     graph.get_edge(self.edge_index).unwrap().line_edge.clone()
                 });
-                ui.add(LineWidget::new(
-                    line_edge.clone(),
-                    self.canvas_state_resource,
-                ))
+                ui.add(
+                    LineWidget::new(line_edge.clone(), self.canvas_state_resource.clone())
+                        .selected(selected),
+                )
+            }
+            EdgeType::Orthogonal => {
+                let orthogonal_edge = self.graph_resource.read_resource(|graph| {
+                    graph
+                        .get_edge(self.edge_index)
+                        .unwrap()
+                        .orthogonal_edge
+                        .clone()
+                });
+                ui.add(
+                    OrthogonalWidget::new(orthogonal_edge, self.canvas_state_resource.clone())
+                        .selected(selected),
+                )
             }
         };
+        self.draw_label(ui, edge_type);
 
         // ui.add(BezierWidget::new(
         //     vec![source_anchor, target_anchor],
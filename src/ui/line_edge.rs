@@ -1,6 +1,12 @@
 use egui::*;
 
-use crate::{graph::anchor::LineAnchor, resource::CanvasStateResource};
+use crate::{
+    canvas::DetailLevel,
+    colors::{edge_selected, edge_stroke},
+    geometry::distance_to_segment,
+    graph::anchor::LineAnchor,
+    resource::CanvasStateResource,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct LineEdge {
@@ -12,11 +18,17 @@ impl LineEdge {
     pub fn new(source: LineAnchor, target: LineAnchor) -> Self {
         Self { source, target }
     }
+
+    /// 画布坐标下点到直线边的距离
+    pub fn distance_to(&self, canvas_pos: Pos2) -> f32 {
+        distance_to_segment(canvas_pos, self.source.canvas_pos, self.target.canvas_pos)
+    }
 }
 
 pub struct LineWidget {
     pub line_edge: LineEdge,
     pub canvas_state_resource: CanvasStateResource,
+    pub selected: bool,
 }
 
 impl LineWidget {
@@ -24,12 +36,26 @@ impl LineWidget {
         Self {
             line_edge,
             canvas_state_resource,
+            selected: false,
+        }
+    }
+
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    fn stroke(&self, ui: &egui::Ui) -> Stroke {
+        if self.selected {
+            Stroke::new(3.0, edge_selected(ui.ctx().theme()))
+        } else {
+            Stroke::new(2.0, edge_stroke(ui.ctx().theme()))
         }
     }
 
     fn draw_arrow(&self, ui: &mut egui::Ui) {
+        let stroke = self.stroke(ui);
         let painter = ui.painter();
-        let stroke = Stroke::new(2.0, Color32::GRAY);
 
         // 将画布坐标转换为屏幕坐标
         let source_screen_pos = self
@@ -39,36 +65,40 @@ impl LineWidget {
             .canvas_state_resource
             .read_resource(|canvas_state| canvas_state.to_screen(self.line_edge.target.canvas_pos));
 
-        // 计算方向向量
-        let dir = target_screen_pos - source_screen_pos;
-        let len = dir.length();
-        if len < f32::EPSILON {
-            return; // 避免出现零长度向量的情况
-        }
-        let dir_norm = dir / len; // 单位化的方向向量
-
-        // 箭头长度（可根据需求调整）
-        let arrow_length = 10.0;
+        draw_arrow_head(painter, source_screen_pos, target_screen_pos, stroke);
+    }
+}
 
-        // 向量旋转函数，用于生成箭头的左右两条短线
-        fn rotate(v: Vec2, angle_rad: f32) -> Vec2 {
-            let (sin, cos) = angle_rad.sin_cos();
-            Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
-        }
+/// 在 `tip` 处画一个沿 `from -> tip` 方向的箭头（屏幕坐标）
+pub fn draw_arrow_head(painter: &Painter, from: Pos2, tip: Pos2, stroke: Stroke) {
+    // 计算方向向量
+    let dir = tip - from;
+    let len = dir.length();
+    if len < f32::EPSILON {
+        return; // 避免出现零长度向量的情况
+    }
+    let dir_norm = dir / len; // 单位化的方向向量
 
-        // 计算箭头两条短线的方向（此处使用 30° 作为示例）
-        let left_dir = rotate(dir_norm, 30_f32.to_radians()) * arrow_length;
-        let right_dir = rotate(dir_norm, -30_f32.to_radians()) * arrow_length;
+    // 箭头长度（可根据需求调整）
+    let arrow_length = 10.0;
 
-        // 在目标端画两条短线
-        painter.line_segment([target_screen_pos, target_screen_pos - left_dir], stroke);
-        painter.line_segment([target_screen_pos, target_screen_pos - right_dir], stroke);
+    // 向量旋转函数，用于生成箭头的左右两条短线
+    fn rotate(v: Vec2, angle_rad: f32) -> Vec2 {
+        let (sin, cos) = angle_rad.sin_cos();
+        Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
     }
+
+    // 计算箭头两条短线的方向（此处使用 30° 作为示例）
+    let left_dir = rotate(dir_norm, 30_f32.to_radians()) * arrow_length;
+    let right_dir = rotate(dir_norm, -30_f32.to_radians()) * arrow_length;
+
+    // 在目标端画两条短线
+    painter.line_segment([tip, tip - left_dir], stroke);
+    painter.line_segment([tip, tip - right_dir], stroke);
 }
 
 impl Widget for LineWidget {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let painter = ui.painter();
         let source_canvas_pos = self.line_edge.source.canvas_pos;
         let target_canvas_pos = self.line_edge.target.canvas_pos;
 
@@ -78,9 +108,17 @@ impl Widget for LineWidget {
         let target_screen_pos = self
             .canvas_state_resource
             .read_resource(|canvas_state| canvas_state.to_screen(target_canvas_pos));
-        let stroke = Stroke::new(2.0, Color32::GRAY);
+        let stroke = self.stroke(ui);
+        let painter = ui.painter();
         painter.line_segment([source_screen_pos, target_screen_pos], stroke);
-        self.draw_arrow(ui);
+        // 缩小时不画箭头
+        if self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.detail_level())
+            == DetailLevel::Full
+        {
+            self.draw_arrow(ui);
+        }
 
         let screen_rect = Rect::from_points(&[source_screen_pos, target_screen_pos]);
         ui.allocate_rect(screen_rect, Sense::click_and_drag())
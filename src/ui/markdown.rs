@@ -0,0 +1,217 @@
+use egui::{Frame, RichText, Ui};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// 行内文字的样式
+#[derive(Clone, Copy, Default)]
+struct InlineStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+    quote: bool,
+    heading: Option<HeadingLevel>,
+}
+
+/// 一段样式相同的行内文字
+struct Run {
+    text: String,
+    style: InlineStyle,
+    link: Option<String>,
+}
+
+/// 把 Markdown 渲染为 egui 控件，支持标题、列表、代码、链接、强调和引用
+#[derive(Default)]
+struct MarkdownRenderer {
+    runs: Vec<Run>,
+    style: InlineStyle,
+    link: Option<String>,
+    /// 列表嵌套，`Some(n)` 为有序列表的下一个序号
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    code_block: Option<String>,
+}
+
+impl MarkdownRenderer {
+    fn push_text(&mut self, text: &str, style: InlineStyle) {
+        self.runs.push(Run {
+            text: text.to_owned(),
+            style: InlineStyle {
+                quote: self.quote_depth > 0,
+                ..style
+            },
+            link: self.link.clone(),
+        });
+    }
+
+    fn rich_text(ui: &Ui, run: &Run) -> RichText {
+        let mut text = RichText::new(&run.text);
+        if let Some(level) = run.style.heading {
+            let body_size = ui
+                .style()
+                .text_styles
+                .get(&egui::TextStyle::Body)
+                .map_or(14.0, |font| font.size);
+            let scale = match level {
+                HeadingLevel::H1 => 1.6,
+                HeadingLevel::H2 => 1.35,
+                HeadingLevel::H3 => 1.2,
+                _ => 1.1,
+            };
+            text = text.size(body_size * scale).strong();
+        }
+        if run.style.strong {
+            text = text.strong();
+        }
+        if run.style.emphasis {
+            text = text.italics();
+        }
+        if run.style.strikethrough {
+            text = text.strikethrough();
+        }
+        if run.style.code {
+            text = text.code();
+        }
+        // 引用中的文字显示为弱化颜色
+        if run.style.quote {
+            text = text.weak();
+        }
+        text
+    }
+
+    /// 输出当前段落收集到的行内文字
+    fn flush(&mut self, ui: &mut Ui) {
+        if self.runs.is_empty() {
+            return;
+        }
+        let runs = std::mem::take(&mut self.runs);
+        let indent = (self.lists.len().saturating_sub(1) + self.quote_depth) as f32 * 16.0;
+        ui.horizontal_wrapped(|ui| {
+            ui.add_space(indent);
+            ui.spacing_mut().item_spacing.x = 0.0;
+            for run in &runs {
+                let text = Self::rich_text(ui, run);
+                match &run.link {
+                    Some(url) => {
+                        ui.hyperlink_to(text, url);
+                    }
+                    None => {
+                        ui.label(text);
+                    }
+                }
+            }
+        });
+    }
+
+    fn render(&mut self, ui: &mut Ui, markdown: &str) {
+        let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        for event in Parser::new_ext(markdown, options) {
+            match event {
+                Event::Start(tag) => self.start(ui, tag),
+                Event::End(tag) => self.end(ui, tag),
+                Event::Text(text) => {
+                    if let Some(code) = self.code_block.as_mut() {
+                        code.push_str(&text);
+                    } else {
+                        self.push_text(&text, self.style);
+                    }
+                }
+                Event::Code(code) => {
+                    let style = InlineStyle {
+                        code: true,
+                        ..self.style
+                    };
+                    self.push_text(&code, style);
+                }
+                Event::SoftBreak => self.push_text(" ", self.style),
+                Event::HardBreak => self.flush(ui),
+                Event::Rule => {
+                    self.flush(ui);
+                    ui.separator();
+                }
+                Event::TaskListMarker(checked) => {
+                    self.push_text(if checked { "☑ " } else { "☐ " }, self.style);
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    self.push_text(&html, self.style);
+                }
+                _ => {}
+            }
+        }
+        self.flush(ui);
+    }
+
+    fn start(&mut self, ui: &mut Ui, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.flush(ui),
+            Tag::Heading { level, .. } => {
+                self.flush(ui);
+                self.style.heading = Some(level);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush(ui);
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush(ui);
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush(ui);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_owned(),
+                };
+                self.push_text(&marker, InlineStyle::default());
+            }
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link { dest_url, .. } => self.link = Some(dest_url.to_string()),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, ui: &mut Ui, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item => self.flush(ui),
+            TagEnd::Heading(_) => {
+                self.flush(ui);
+                self.style.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code_block.take().unwrap_or_default();
+                Frame::group(ui.style())
+                    .fill(ui.visuals().extreme_bg_color)
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(RichText::new(code.trim_end()).monospace());
+                    });
+            }
+            TagEnd::List(_) => {
+                self.flush(ui);
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strikethrough = false,
+            TagEnd::Link => self.link = None,
+            _ => {}
+        }
+    }
+}
+
+/// 渲染一段 Markdown 文本
+pub fn markdown_ui(ui: &mut Ui, markdown: &str) {
+    MarkdownRenderer::default().render(ui, markdown);
+}
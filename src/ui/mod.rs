@@ -1,9 +1,13 @@
 pub mod bezier;
+pub mod bookmark_panel;
 pub mod canvas;
 pub mod edge;
 pub mod edge_trait;
 pub mod helpers;
 pub mod line_edge;
+pub mod markdown;
 pub mod node;
 pub mod node_render_observer;
+pub mod note_panel;
+pub mod orthogonal_edge;
 pub mod temp_edge;
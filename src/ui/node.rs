@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use crate::canvas::DetailLevel;
+use crate::graph::helpers::{collapse_toggle_center, COLLAPSE_TOGGLE_RADIUS};
 use crate::graph::node_observer::NodeObserver;
 use crate::graph::render_info::NodeRenderInfo;
 use crate::graph::selection::GraphSelection;
@@ -80,16 +82,7 @@ impl NodeWidget {
             });
         }
 
-        if ui.input(|i| i.key_pressed(egui::Key::Backspace) || i.key_pressed(egui::Key::Delete)) {
-            self.graph_resource.with_resource(|graph| {
-                if let GraphSelection::Node(selected_nodes) = &graph.selected {
-                    if selected_nodes.contains(&self.node_index) && graph.editing_node.is_none() {
-                        println!("node deleted: {:?}", self.node_index);
-                        graph.remove_node(self.node_index);
-                    }
-                }
-            });
-        }
+        // 删除选中节点由 InputStateManager 统一处理，保证多选删除是一个撤销步骤
 
         // Ctrl + Enter
         if ui.input(|i| {
@@ -285,29 +278,40 @@ impl Widget for NodeWidget {
             .read_resource(|canvas_state| canvas_state.transform.scaling);
         // let node = self.graph.get_node_mut(self.node_id).unwrap();
 
-        let text = {
+        let (text, fixed_width) = {
             self.graph_resource.with_resource(|graph| {
                 let node = graph.get_node(self.node_index).unwrap();
-                node.text.to_string()
+                (node.text.to_string(), node.width)
             })
         };
         let font_size = 20.0 * scale_level; // 你可以调整这个数值
                                             // let font_size = 20.0;
         let font = egui::FontId::new(font_size, egui::FontFamily::Proportional);
 
-        let galley = ui
-            .painter()
-            .layout_no_wrap(text.clone(), font.clone(), egui::Color32::RED);
-        let text_size = galley.size();
-        // let text_size = egui::Vec2::new(100.0, 100.0);
-
-        let min_width = 60.0 * scale_level;
-        // let min_height = 40.0 * self.canvas_state.scale;
-
-        let desired_size = egui::vec2(
-            (text_size.x + 20.0 * scale_level).max(min_width),
-            text_size.y + 10.0 * scale_level,
-        );
+        // 正在编辑的节点总是完整绘制
+        let level = if self
+            .graph_resource
+            .read_resource(|graph| graph.get_editing_node())
+            == Some(self.node_index)
+        {
+            DetailLevel::Full
+        } else {
+            self.canvas_state_resource
+                .read_resource(|canvas_state| canvas_state.detail_level())
+        };
+        let desired_size = if level == DetailLevel::Full {
+            node_size(ui, &text, &font, fixed_width, scale_level)
+        } else {
+            // 缩小时不排版文字，沿用完整绘制时的画布尺寸，命中测试的范围保持不变
+            let canvas_size = self
+                .graph_resource
+                .read_resource(|graph| graph.node_size(self.node_index))
+                .unwrap_or_else(|| {
+                    let font = egui::FontId::new(20.0, egui::FontFamily::Proportional);
+                    node_size(ui, &text, &font, fixed_width, 1.0)
+                });
+            canvas_size * scale_level
+        };
 
         let screen_pos = {
             self.graph_resource.with_resource(|graph| {
@@ -323,113 +327,141 @@ impl Widget for NodeWidget {
 
         self.setup_actions(&response, ui);
 
+        // 悬停时显示备注的前几行
+        let note = self
+            .graph_resource
+            .read_resource(|graph| graph.get_node(self.node_index).unwrap().note.clone());
+        let response = match note_preview(&note) {
+            Some(preview) => response.on_hover_text(preview),
+            None => response,
+        };
+
         let selected_rect = rect.expand(5.0 * scale_level);
         if ui.is_rect_visible(rect) {
-            let painter = ui.painter();
-
-            // 绘制包围矩形
-            painter.rect(
-                rect,
-                egui::CornerRadius::same(5),
-                egui::Color32::TRANSPARENT,
-                Stroke::new(1.0, egui::Color32::ORANGE),
-                egui::StrokeKind::Outside,
-            );
+            if level == DetailLevel::Full {
+                let painter = ui.painter();
 
-            // let (response, painter) = ui.allocate_painter(desired_size, Sense::click_and_drag());
-            // println!("screen_pos: {:?}", screen_pos);
-            // 根据文本大小创建矩形区域
-
-            // let stroke_width = 3.0 * canvas_state.scale;
-            let stroke_width = 1.0;
-
-            if self.graph_resource.read_resource(|graph| {
-                if let GraphSelection::Node(selected_nodes) = &graph.selected {
-                    selected_nodes.contains(&self.node_index)
-                } else {
-                    false
-                }
-            }) {
+                // 绘制包围矩形
                 painter.rect(
-                    selected_rect,
+                    rect,
                     egui::CornerRadius::same(5),
                     egui::Color32::TRANSPARENT,
-                    egui::Stroke::new(2.0, node_border_selected(ui.ctx().theme())), // 将线宽从20.0改为1.0
+                    Stroke::new(1.0, egui::Color32::ORANGE),
                     egui::StrokeKind::Outside,
                 );
-            }
-            // 绘制边框
-            painter.rect(
-                rect,
-                egui::CornerRadius::same(5),
-                node_background(ui.ctx().theme()),
-                egui::Stroke::new(stroke_width, node_border(ui.ctx().theme())), // 将线宽从20.0改为1.0
-                egui::StrokeKind::Outside,
-            );
 
-            // 根据rect计算文本位置，使得文本居中
-            let text_pos = rect.center();
+                // let (response, painter) = ui.allocate_painter(desired_size, Sense::click_and_drag());
+                // println!("screen_pos: {:?}", screen_pos);
+                // 根据文本大小创建矩形区域
 
-            // 当前节点正在编辑
-            if self
-                .graph_resource
-                .read_resource(|graph| graph.get_editing_node())
-                == Some(self.node_index)
-            {
-                let mut text = {
-                    self.graph_resource.with_resource(|graph| {
-                        let node = graph.get_node(self.node_index).unwrap();
-                        node.text.to_string()
-                    })
-                };
-                // let mut response = ui.text_edit_singleline(&mut text);
-                let edit_response = ui.put(
+                // let stroke_width = 3.0 * canvas_state.scale;
+                let stroke_width = 1.0;
+
+                if self.graph_resource.read_resource(|graph| {
+                    if let GraphSelection::Node(selected_nodes) = &graph.selected {
+                        selected_nodes.contains(&self.node_index)
+                    } else {
+                        false
+                    }
+                }) {
+                    painter.rect(
+                        selected_rect,
+                        egui::CornerRadius::same(5),
+                        egui::Color32::TRANSPARENT,
+                        egui::Stroke::new(2.0, node_border_selected(ui.ctx().theme())), // 将线宽从20.0改为1.0
+                        egui::StrokeKind::Outside,
+                    );
+                }
+                // 绘制边框
+                painter.rect(
                     rect,
-                    egui::TextEdit::multiline(&mut text)
-                        // .min_size(egui::vec2(min_width, min_height))
-                        .desired_rows(1)
-                        // .min_size(egui::vec2(min_width, 2.0))
-                        .font(font)
-                        .text_color(egui::Color32::RED)
-                        .background_color(node_background(ui.ctx().theme()))
-                        // .margin(
-                        //     egui::vec2(10.0, 0.0)
-                        //         * canvas_state_resource
-                        //             .read_resource(|canvas_state| canvas_state.scale),
-                        // )
-                        .horizontal_align(egui::Align::Center)
-                        .vertical_align(egui::Align::Center),
+                    egui::CornerRadius::same(5),
+                    node_background(ui.ctx().theme()),
+                    egui::Stroke::new(stroke_width, node_border(ui.ctx().theme())), // 将线宽从20.0改为1.0
+                    egui::StrokeKind::Outside,
                 );
 
-                // if edit_response.lost_focus() {
-                //     graph_resource.with_resource(|graph| {
-                //         graph.set_editing_node(None);
-                //     });
-                // }
+                // 根据rect计算文本位置，使得文本居中
+                let text_pos = rect.center();
+
+                // 当前节点正在编辑
+                if self
+                    .graph_resource
+                    .read_resource(|graph| graph.get_editing_node())
+                    == Some(self.node_index)
+                {
+                    let mut text = {
+                        self.graph_resource.with_resource(|graph| {
+                            let node = graph.get_node(self.node_index).unwrap();
+                            node.text.to_string()
+                        })
+                    };
+                    // let mut response = ui.text_edit_singleline(&mut text);
+                    let edit_response = ui.put(
+                        rect,
+                        egui::TextEdit::multiline(&mut text)
+                            // .min_size(egui::vec2(min_width, min_height))
+                            .desired_rows(1)
+                            // .min_size(egui::vec2(min_width, 2.0))
+                            .font(font)
+                            .text_color(egui::Color32::RED)
+                            .background_color(node_background(ui.ctx().theme()))
+                            // .margin(
+                            //     egui::vec2(10.0, 0.0)
+                            //         * canvas_state_resource
+                            //             .read_resource(|canvas_state| canvas_state.scale),
+                            // )
+                            .horizontal_align(egui::Align::Center)
+                            .vertical_align(egui::Align::Center),
+                    );
+
+                    // if edit_response.lost_focus() {
+                    //     graph_resource.with_resource(|graph| {
+                    //         graph.set_editing_node(None);
+                    //     });
+                    // }
+
+                    edit_response.request_focus();
+                    self.graph_resource.with_resource(|graph| {
+                        graph.set_node_text(self.node_index, text);
+                    });
+                } else {
+                    // 绘制文本
+                    painter.text(
+                        text_pos,
+                        egui::Align2::CENTER_CENTER,
+                        text,
+                        font.clone(),
+                        egui::Color32::RED,
+                    );
+                }
 
-                edit_response.request_focus();
-                self.graph_resource.with_resource(|graph| {
-                    let node = graph.get_node_mut(self.node_index).unwrap();
-                    node.text = text;
-                });
+                // 在右上角绘制节点ID
+                self.draw_node_id(ui, &response);
+                if !note.trim().is_empty() {
+                    self.draw_note_badge(ui, &response);
+                }
             } else {
-                // 绘制文本
-                painter.text(
-                    text_pos,
-                    egui::Align2::CENTER_CENTER,
-                    text,
-                    font.clone(),
-                    egui::Color32::RED,
-                );
+                self.draw_simplified(ui, rect, level);
             }
 
-            // 在右上角绘制节点ID
-            self.draw_node_id(ui, &response);
-
             let canvas_rect = self
                 .canvas_state_resource
                 .read_resource(|canvas_state| canvas_state.to_canvas_rect(rect));
+            let (shows_toggle, collapsed) = self.graph_resource.read_resource(|graph| {
+                (
+                    graph.shows_collapse_toggle(self.node_index),
+                    graph
+                        .get_node(self.node_index)
+                        .is_some_and(|node| node.collapsed),
+                )
+            });
+            if shows_toggle && level != DetailLevel::Dots {
+                self.draw_collapse_toggle(ui, canvas_rect, collapsed);
+            }
             let render_info = NodeRenderInfo { canvas_rect };
+            self.graph_resource
+                .with_resource(|graph| graph.set_node_size(self.node_index, canvas_rect.size()));
 
             self.observers.iter().for_each(|observer| {
                 observer.on_node_changed(self.node_index, render_info);
@@ -450,7 +482,142 @@ impl Widget for NodeWidget {
     }
 }
 
+/// 完整绘制时节点的屏幕尺寸：文字加上内边距
+fn node_size(
+    ui: &egui::Ui,
+    text: &str,
+    font: &egui::FontId,
+    fixed_width: Option<f32>,
+    scale_level: f32,
+) -> egui::Vec2 {
+    let galley = ui
+        .painter()
+        .layout_no_wrap(text.to_owned(), font.clone(), egui::Color32::RED);
+    let text_size = galley.size();
+
+    // 设置了固定宽度时以它为最小宽度，文本更长时仍然撑开
+    let min_width = fixed_width.unwrap_or(60.0).max(60.0) * scale_level;
+
+    egui::vec2(
+        (text_size.x + 20.0 * scale_level).max(min_width),
+        text_size.y + 10.0 * scale_level,
+    )
+}
+
+/// 备注预览：取前几行非空文字
+fn note_preview(note: &str) -> Option<String> {
+    const MAX_LINES: usize = 3;
+    const MAX_CHARS: usize = 60;
+
+    let mut lines = note.lines().map(str::trim).filter(|line| !line.is_empty());
+    let mut preview = lines
+        .by_ref()
+        .take(MAX_LINES)
+        .map(|line| {
+            if line.chars().count() > MAX_CHARS {
+                format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<Vec<_>>();
+    if preview.is_empty() {
+        return None;
+    }
+    if lines.next().is_some() {
+        preview.push("…".to_owned());
+    }
+    Some(preview.join("\n"))
+}
+
 impl NodeWidget {
+    /// 缩小时的简化绘制：不带文字的方框，或者只是一个色块
+    fn draw_simplified(&self, ui: &mut egui::Ui, rect: egui::Rect, level: DetailLevel) {
+        let selected = self
+            .graph_resource
+            .read_resource(|graph| graph.is_node_selected(self.node_index));
+        let theme = ui.ctx().theme();
+        let painter = ui.painter();
+        if level == DetailLevel::Dots {
+            let color = if selected {
+                node_border_selected(theme)
+            } else {
+                node_border(theme)
+            };
+            painter.rect_filled(rect, egui::CornerRadius::ZERO, color);
+            return;
+        }
+
+        let stroke = if selected {
+            Stroke::new(2.0, node_border_selected(theme))
+        } else {
+            Stroke::new(1.0, node_border(theme))
+        };
+        painter.rect(
+            rect,
+            egui::CornerRadius::same(2),
+            node_background(theme),
+            stroke,
+            egui::StrokeKind::Outside,
+        );
+    }
+
+    // 在节点左上角绘制备注标记
+    fn draw_note_badge(&self, ui: &mut egui::Ui, node_response: &egui::Response) {
+        let scale_level = self
+            .canvas_state_resource
+            .read_resource(|canvas_state| canvas_state.transform.scaling);
+        let radius = 6.0 * scale_level;
+        let center = node_response.rect.left_top() + egui::vec2(radius, radius) * 0.5;
+        let painter = ui.painter();
+        painter.circle_filled(center, radius, node_border(ui.ctx().theme()));
+
+        // 三条短横线表示文字
+        let stroke = Stroke::new((1.0 * scale_level).max(0.5), egui::Color32::WHITE);
+        for i in -1..=1 {
+            let y = center.y + i as f32 * radius * 0.4;
+            painter.line_segment(
+                [
+                    egui::pos2(center.x - radius * 0.45, y),
+                    egui::pos2(center.x + radius * 0.45, y),
+                ],
+                stroke,
+            );
+        }
+    }
+
+    /// 节点右侧的折叠按钮，折叠时显示加号
+    fn draw_collapse_toggle(&self, ui: &mut egui::Ui, canvas_rect: egui::Rect, collapsed: bool) {
+        let (center, scale_level) = self.canvas_state_resource.read_resource(|canvas_state| {
+            (
+                canvas_state.to_screen(collapse_toggle_center(canvas_rect)),
+                canvas_state.transform.scaling,
+            )
+        });
+        let radius = COLLAPSE_TOGGLE_RADIUS * scale_level;
+        let painter = ui.painter();
+        let theme = ui.ctx().theme();
+        painter.circle(
+            center,
+            radius,
+            node_background(theme),
+            Stroke::new((1.0 * scale_level).max(0.5), node_border(theme)),
+        );
+
+        let stroke = Stroke::new((1.5 * scale_level).max(0.5), node_border(theme));
+        let arm = radius * 0.5;
+        painter.line_segment(
+            [center - egui::vec2(arm, 0.0), center + egui::vec2(arm, 0.0)],
+            stroke,
+        );
+        if collapsed {
+            painter.line_segment(
+                [center - egui::vec2(0.0, arm), center + egui::vec2(0.0, arm)],
+                stroke,
+            );
+        }
+    }
+
     fn draw_node_id(&self, ui: &mut egui::Ui, node_response: &egui::Response) {
         let scale_level = (self
             .canvas_state_resource
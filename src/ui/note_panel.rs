@@ -0,0 +1,95 @@
+use egui::{RichText, ScrollArea, TextEdit};
+use petgraph::graph::NodeIndex;
+
+use crate::resource::GraphResource;
+
+use super::markdown::markdown_ui;
+
+/// 节点备注侧边栏，显示并编辑唯一选中节点的备注
+#[derive(Debug)]
+pub struct NotePanel {
+    pub graph_resource: GraphResource,
+    /// 为 true 时显示编辑框，否则显示渲染后的 Markdown
+    pub editing: bool,
+    /// 上一帧显示的节点，切换节点时回到预览
+    node_index: Option<NodeIndex>,
+}
+
+impl NotePanel {
+    pub fn new(graph_resource: GraphResource) -> Self {
+        Self {
+            graph_resource,
+            editing: false,
+            node_index: None,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("note_panel")
+            .resizable(true)
+            .default_width(280.0)
+            .show(ctx, |ui| self.ui(ui));
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let selected =
+            self.graph_resource
+                .read_resource(|graph| match graph.get_selected_nodes()[..] {
+                    [node_index] => graph
+                        .get_node(node_index)
+                        .map(|node| (node_index, node.text.clone(), node.note.clone())),
+                    _ => None,
+                });
+
+        let Some((node_index, title, mut note)) = selected else {
+            self.node_index = None;
+            self.editing = false;
+            ui.heading("Note");
+            ui.label(RichText::new("Select a single node to view its note.").weak());
+            return;
+        };
+
+        if self.node_index != Some(node_index) {
+            self.node_index = Some(node_index);
+            self.editing = false;
+            self.graph_resource
+                .with_resource(|graph| graph.history.seal());
+        }
+
+        ui.horizontal(|ui| {
+            ui.heading(if title.is_empty() { "Note" } else { &title });
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .selectable_label(self.editing, "Edit")
+                    .on_hover_text("Edit the note as Markdown")
+                    .clicked()
+                {
+                    self.editing = !self.editing;
+                    // 一次编辑的输入合并为一个撤销步骤
+                    self.graph_resource
+                        .with_resource(|graph| graph.history.seal());
+                }
+            });
+        });
+        ui.separator();
+
+        ScrollArea::vertical().show(ui, |ui| {
+            if self.editing {
+                let response = ui.add(
+                    TextEdit::multiline(&mut note)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(12),
+                );
+                if response.changed() {
+                    self.graph_resource
+                        .with_resource(|graph| graph.set_node_note(node_index, note));
+                }
+            } else if note.trim().is_empty() {
+                ui.label(RichText::new("No note yet. Click Edit to add one.").weak());
+            } else {
+                markdown_ui(ui, &note);
+            }
+        });
+    }
+}
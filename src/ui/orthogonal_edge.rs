@@ -0,0 +1,132 @@
+use egui::*;
+
+use crate::{
+    canvas::DetailLevel,
+    colors::{edge_selected, edge_stroke},
+    geometry::distance_to_polyline,
+    resource::CanvasStateResource,
+};
+
+use super::line_edge::draw_arrow_head;
+
+/// 拐角圆角半径（画布坐标）
+const CORNER_RADIUS: f32 = 8.0;
+
+/// 直角折线边，`points` 包含首尾端点
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct OrthogonalEdge {
+    pub points: Vec<Pos2>,
+}
+
+impl OrthogonalEdge {
+    pub fn new(points: Vec<Pos2>) -> Self {
+        Self { points }
+    }
+
+    /// 画布坐标下点到折线的距离
+    pub fn distance_to(&self, canvas_pos: Pos2) -> f32 {
+        distance_to_polyline(canvas_pos, &self.points)
+    }
+}
+
+pub struct OrthogonalWidget {
+    pub orthogonal_edge: OrthogonalEdge,
+    pub canvas_state_resource: CanvasStateResource,
+    pub selected: bool,
+}
+
+impl OrthogonalWidget {
+    pub fn new(
+        orthogonal_edge: OrthogonalEdge,
+        canvas_state_resource: CanvasStateResource,
+    ) -> Self {
+        Self {
+            orthogonal_edge,
+            canvas_state_resource,
+            selected: false,
+        }
+    }
+
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    fn stroke(&self, ui: &egui::Ui) -> Stroke {
+        if self.selected {
+            Stroke::new(3.0, edge_selected(ui.ctx().theme()))
+        } else {
+            Stroke::new(2.0, edge_stroke(ui.ctx().theme()))
+        }
+    }
+}
+
+/// 把折线的拐角替换为圆弧（用二次贝塞尔近似）
+fn rounded_polyline(points: &[Pos2], radius: f32) -> Vec<Pos2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut result = vec![points[0]];
+    for window in points.windows(3) {
+        let (prev, corner, next) = (window[0], window[1], window[2]);
+        let in_len = (corner - prev).length();
+        let out_len = (next - corner).length();
+        // 圆角不能超过相邻线段的一半
+        let r = radius.min(in_len / 2.0).min(out_len / 2.0);
+        if r < 0.5 {
+            result.push(corner);
+            continue;
+        }
+        let start = corner - (corner - prev) / in_len * r;
+        let end = corner + (next - corner) / out_len * r;
+        const SEGMENTS: usize = 6;
+        for i in 0..=SEGMENTS {
+            let t = i as f32 / SEGMENTS as f32;
+            let a = start.lerp(corner, t);
+            let b = corner.lerp(end, t);
+            result.push(a.lerp(b, t));
+        }
+    }
+    result.push(points[points.len() - 1]);
+    result
+}
+
+impl Widget for OrthogonalWidget {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let (screen_points, scaling, level) =
+            self.canvas_state_resource.read_resource(|canvas_state| {
+                (
+                    self.orthogonal_edge
+                        .points
+                        .iter()
+                        .map(|p| canvas_state.to_screen(*p))
+                        .collect::<Vec<_>>(),
+                    canvas_state.transform.scaling,
+                    canvas_state.detail_level(),
+                )
+            });
+        if screen_points.len() < 2 {
+            return ui.allocate_rect(Rect::NOTHING, Sense::hover());
+        }
+
+        let stroke = self.stroke(ui);
+        let painter = ui.painter();
+        // 缩小时画不带圆角和箭头的折线
+        if level != DetailLevel::Full {
+            painter.add(Shape::line(screen_points.clone(), stroke));
+        } else {
+            painter.add(Shape::line(
+                rounded_polyline(&screen_points, CORNER_RADIUS * scaling),
+                stroke,
+            ));
+
+            let tip = screen_points[screen_points.len() - 1];
+            let from = screen_points[screen_points.len() - 2];
+            draw_arrow_head(painter, from, tip, stroke);
+        }
+
+        let screen_rect = Rect::from_points(&screen_points);
+        ui.allocate_rect(screen_rect, Sense::click_and_drag())
+    }
+}
@@ -64,9 +64,7 @@ impl<'a> Widget for TempEdgeWidget<'a> {
         println!("screen_rect: {:?}", screen_rect);
         let response = ui.allocate_rect(screen_rect, Sense::click_and_drag());
 
-        let edge_type = self
-            .graph_resource
-            .read_resource(|graph| graph.edge_type.clone());
+        let edge_type = self.graph_resource.read_resource(|graph| graph.edge_type);
         match edge_type {
             EdgeType::Bezier => {
                 // 获取节点中心点
@@ -86,7 +84,8 @@ impl<'a> Widget for TempEdgeWidget<'a> {
                     self.canvas_state_resource,
                 ));
             }
-            EdgeType::Line => {
+            // 拖拽中的临时边还没有目标节点，折线边也先画成直线
+            EdgeType::Line | EdgeType::Orthogonal => {
                 ui.add(LineWidget::new(
                     self.temp_edge.line_edge.clone(),
                     self.canvas_state_resource,